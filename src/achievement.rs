@@ -0,0 +1,272 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::event::{EventSubscriber, GameEvent};
+use crate::game_state::play::PlayerId;
+use crate::profile::{Participant, ProfileStore};
+use crate::{ACHIEVEMENT_SPEED_TOLERANCE, ACHIEVEMENT_TOAST_TIME};
+
+/// The achievements that come with the game.
+const ACHIEVEMENTS: &str = include_str!("../achievements.json");
+/// The file inside of the config directory that replaces the achievements of the game.
+const ACHIEVEMENT_FILE: &str = "pong-rs/achievements.json";
+
+/// Something a player can reach in a match, defined in the achievements file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Achievement {
+    /// Stored in the profiles, so it shouldn't change once players have unlocked it.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// What a player has to do for an achievement.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// Win a match. Without limits any win counts.
+    Win {
+        /// The least points of the winner.
+        min_points: Option<u32>,
+        /// The most points the opponent may have scored.
+        max_opponent_points: Option<u32>,
+        /// The least points the winner was behind at some time of the match.
+        min_comeback: Option<u32>,
+    },
+    /// Return the ball. Without limits any return counts.
+    Return {
+        /// The least speed of the ball afterwards.
+        min_speed: Option<f64>,
+        /// The ball has the max speed of the config afterwards.
+        #[serde(default)]
+        at_max_speed: bool,
+    },
+    /// Play a rally with at least this many hits of both players.
+    Rally { min_hits: u32 },
+}
+
+/// A notification about an unlocked achievement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    /// The profile that unlocked the achievement.
+    pub profile: String,
+    pub name: String,
+    pub description: String,
+    /// The time in seconds the toast is still shown.
+    time_left: f64,
+}
+
+/// Finds the achievements the players reach in the events of their matches
+/// and keeps the toasts of the unlocked ones.
+#[derive(Debug)]
+pub struct Achievements {
+    definitions: Vec<Achievement>,
+    /// Who plays the running match, None outside of a match.
+    players: Option<[Participant; 2]>,
+    /// The most points player one and two have been behind in the match.
+    deficits: [u32; 2],
+    rally_hits: u32,
+    /// The profiles with the ids of the achievements they have reached since they were last unlocked.
+    reached: Vec<(String, String)>,
+    toasts: VecDeque<Toast>,
+}
+
+impl Achievements {
+    pub fn new(definitions: Vec<Achievement>) -> Self {
+        Self {
+            definitions,
+            players: None,
+            deficits: [0, 0],
+            rally_hits: 0,
+            reached: Vec::new(),
+            toasts: VecDeque::new(),
+        }
+    }
+
+    /// Reads the definitions of an achievements file, a list of [`Achievement`]s with unique ids.
+    pub fn parse(file: &str) -> io::Result<Vec<Achievement>> {
+        let definitions: Vec<Achievement> = serde_json::from_str(file)?;
+        let mut ids = HashSet::new();
+        if let Some(achievement) = definitions
+            .iter()
+            .find(|achievement| !ids.insert(achievement.id.as_str()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The achievement id {} is used twice", achievement.id),
+            ));
+        }
+        Ok(definitions)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = fs::read_to_string(path)?;
+        Ok(Self::new(Self::parse(&file)?))
+    }
+
+    /// The location of the achievements file that replaces the achievements of the game.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(ACHIEVEMENT_FILE))
+    }
+
+    /// Loads the achievements file of the config directory, or the achievements of the game without one.
+    pub fn load_or_default() -> Self {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        match Self::load(&path) {
+            Ok(achievements) => achievements,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                eprintln!(
+                    "Unable to load the achievements {}, using the achievements of the game: {}",
+                    path.display(),
+                    err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn definitions(&self) -> &[Achievement] {
+        &self.definitions
+    }
+
+    /// Unlocks the achievements the profiles have reached since the last call
+    /// and shows a toast for every one they didn't have before.
+    /// Should be called after the events are dispatched, the profiles are a separate subscriber.
+    pub fn unlock_reached(&mut self, profiles: &mut ProfileStore) {
+        for (profile, achievement) in mem::take(&mut self.reached) {
+            match profiles.unlock(&profile, &achievement) {
+                Ok(true) => self.show(&profile, &achievement),
+                Ok(false) => {}
+                Err(err) => eprintln!("Unable to save the profiles: {}", err),
+            }
+        }
+    }
+
+    /// Shows a toast for an achievement the profile has unlocked.
+    pub fn show(&mut self, profile: &str, id: &str) {
+        let Some(achievement) = self
+            .definitions
+            .iter()
+            .find(|achievement| achievement.id == id)
+        else {
+            return;
+        };
+        self.toasts.push_back(Toast {
+            profile: profile.to_string(),
+            name: achievement.name.clone(),
+            description: achievement.description.clone(),
+            time_left: ACHIEVEMENT_TOAST_TIME,
+        });
+    }
+
+    /// The toast that is shown now, the others wait until it is gone.
+    pub fn toast(&self) -> Option<&Toast> {
+        self.toasts.front()
+    }
+
+    /// Remembers every achievement with the condition for the player, if it plays with a profile.
+    fn reach(&mut self, player: &PlayerId, condition: impl Fn(&Condition) -> bool) {
+        let Some(players) = &self.players else {
+            return;
+        };
+        let Participant::Profile(name) = &players[player.index()] else {
+            return;
+        };
+        for achievement in &self.definitions {
+            if condition(&achievement.condition) {
+                self.reached.push((name.clone(), achievement.id.clone()));
+            }
+        }
+    }
+}
+
+impl Default for Achievements {
+    fn default() -> Self {
+        Self::new(Self::parse(ACHIEVEMENTS).expect("Unable to load the achievements of the game!"))
+    }
+}
+
+impl EventSubscriber for Achievements {
+    fn handle(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::MatchStarted { players } => {
+                self.players = Some(players.clone());
+                self.deficits = [0, 0];
+                self.rally_hits = 0;
+            }
+            GameEvent::PaddleHit {
+                player,
+                speed,
+                max_speed,
+            } => {
+                self.rally_hits += 1;
+                let hits = self.rally_hits;
+                self.reach(player, |condition| match condition {
+                    Condition::Return {
+                        min_speed,
+                        at_max_speed,
+                    } => {
+                        min_speed.is_none_or(|min| *speed >= min - ACHIEVEMENT_SPEED_TOLERANCE)
+                            && (!at_max_speed || *speed >= max_speed - ACHIEVEMENT_SPEED_TOLERANCE)
+                    }
+                    _ => false,
+                });
+                // A rally counts for both players
+                for player in [PlayerId::One, PlayerId::Two] {
+                    self.reach(&player, |condition| {
+                        matches!(condition, Condition::Rally { min_hits } if hits >= *min_hits)
+                    });
+                }
+            }
+            GameEvent::Scored { score, .. } => {
+                self.rally_hits = 0;
+                let (one, two) = *score;
+                self.deficits[0] = self.deficits[0].max(two.saturating_sub(one));
+                self.deficits[1] = self.deficits[1].max(one.saturating_sub(two));
+            }
+            GameEvent::MatchOver(result) => {
+                let winner = result.winner.index();
+                let (points, opponent_points) = match result.winner {
+                    PlayerId::One => result.score,
+                    PlayerId::Two => (result.score.1, result.score.0),
+                };
+                let comeback = self.deficits[winner];
+                self.reach(&result.winner, |condition| match condition {
+                    Condition::Win {
+                        min_points,
+                        max_opponent_points,
+                        min_comeback,
+                    } => {
+                        min_points.is_none_or(|min| points >= min)
+                            && max_opponent_points.is_none_or(|max| opponent_points <= max)
+                            && min_comeback.is_none_or(|min| comeback >= min)
+                    }
+                    _ => false,
+                });
+                self.players = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Counts down the time of the shown toast.
+    fn advance(&mut self, dt: f64) {
+        if let Some(toast) = self.toasts.front_mut() {
+            toast.time_left -= dt;
+            if toast.time_left <= 0.0 {
+                self.toasts.pop_front();
+            }
+        }
+    }
+}
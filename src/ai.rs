@@ -0,0 +1,300 @@
+use piston::UpdateArgs;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::Arena;
+use crate::ball::Ball;
+use crate::config::{BallConfig, Config};
+use crate::game_state::play::PlayerId;
+use crate::paddle::{Paddle, PaddleInput};
+use crate::{
+    AI_DEAD_ZONE, AI_LINEAR_DEAD_ZONE, AI_PREDICTION_STEP, AI_PREDICTION_TIME, BALL_SIZE,
+    COURT_SIZE, PADDLE_SIZE,
+};
+
+/// How well a computer player plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    /// The name of the computer player, shown instead of a profile name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "CPU Easy",
+            Difficulty::Medium => "CPU Medium",
+            Difficulty::Hard => "CPU Hard",
+        }
+    }
+
+    /// The time in seconds between two decisions where to move.
+    fn reaction_time(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.4,
+            Difficulty::Medium => 0.2,
+            Difficulty::Hard => 0.05,
+        }
+    }
+
+    /// The most the computer player misjudges where the ball arrives.
+    fn aim_error(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 45.0,
+            Difficulty::Medium => 20.0,
+            Difficulty::Hard => 6.0,
+        }
+    }
+
+    /// The rating before the first rated match.
+    pub fn initial_rating(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 1100.0,
+            Difficulty::Medium => 1400.0,
+            Difficulty::Hard => 1700.0,
+        }
+    }
+}
+
+/// The number of weights of a linear controller, see [`Controller::Linear`].
+pub const LINEAR_FEATURES: usize = 6;
+
+/// How a computer player decides where to move its paddle.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Controller {
+    /// The computer player of the game with the difficulty.
+    Builtin(Difficulty),
+    /// Moves to where it expects the ball like the built in computer players, with other settings.
+    Scripted {
+        /// The time in seconds between two decisions where to move.
+        reaction_time: f64,
+        /// The most it misjudges where the ball arrives.
+        aim_error: f64,
+    },
+    /// Moves by a weighted sum of what it sees, for example with weights learned by training.
+    /// The features are a constant 1, the height of the ball above the paddle center,
+    /// the vertical and the approaching speed of the ball, its distance to the paddle
+    /// and the speed of the paddle, each scaled to about -1 to 1.
+    Linear { weights: [f64; LINEAR_FEATURES] },
+}
+
+impl Controller {
+    /// Whether the settings can be used, the times and errors can't be negative.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Controller::Builtin(_) => true,
+            Controller::Scripted {
+                reaction_time,
+                aim_error,
+            } => *reaction_time >= 0.0 && *aim_error >= 0.0,
+            Controller::Linear { weights } => weights.iter().all(|weight| weight.is_finite()),
+        }
+    }
+}
+
+/// A computer player that moves a paddle like a human pressing the buttons.
+#[derive(Clone, Debug)]
+pub struct Bot {
+    controller: Controller,
+    /// The y position the center of the paddle moves to.
+    target: f64,
+    /// The time until the next decision in seconds.
+    next_decision: f64,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self::with_controller(Controller::Builtin(difficulty))
+    }
+
+    pub fn with_controller(controller: Controller) -> Self {
+        Self {
+            controller,
+            target: COURT_SIZE.1 as f64 / 2.0,
+            next_decision: 0.0,
+        }
+    }
+
+    pub fn controller(&self) -> &Controller {
+        &self.controller
+    }
+
+    /// Sets the input of the paddle of the player, like a human pressing the buttons.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update<R: Rng>(
+        &mut self,
+        player: &PlayerId,
+        paddle: &mut Paddle,
+        ball: &Ball,
+        arena: &Arena,
+        config: &Config,
+        rng: &mut R,
+        dt: f64,
+    ) {
+        let (reaction_time, aim_error) = match &self.controller {
+            Controller::Builtin(difficulty) => (difficulty.reaction_time(), difficulty.aim_error()),
+            Controller::Scripted {
+                reaction_time,
+                aim_error,
+            } => (*reaction_time, *aim_error),
+            Controller::Linear { weights } => {
+                *paddle.input_mut() = Self::linear_input(weights, player, paddle, ball, config);
+                return;
+            }
+        };
+
+        self.next_decision -= dt;
+        if self.next_decision <= 0.0 {
+            self.next_decision = reaction_time;
+            self.target =
+                Self::choose_target(player, paddle, ball, arena, &config.ball, aim_error, rng);
+        }
+
+        // Let the paddle coast once the friction stops it at the target
+        let difference = self.target - (paddle.y() + PADDLE_SIZE.1 as f64 / 2.0);
+        let stopping_distance = paddle.velocity().powi(2) / (2.0 * config.paddle.friction);
+        let moving_towards = paddle.velocity() * difference > 0.0;
+        *paddle.input_mut() = if difference.abs() <= AI_DEAD_ZONE
+            || (moving_towards && stopping_distance >= difference.abs())
+        {
+            PaddleInput::None
+        } else if difference > 0.0 {
+            PaddleInput::Up
+        } else {
+            PaddleInput::Down
+        };
+    }
+
+    /// Where the ball will reach the paddle with a random error up to the aim error,
+    /// or the middle of the court if the ball moves away.
+    fn choose_target<R: Rng>(
+        player: &PlayerId,
+        paddle: &Paddle,
+        ball: &Ball,
+        arena: &Arena,
+        config: &BallConfig,
+        aim_error: f64,
+        rng: &mut R,
+    ) -> f64 {
+        if defending_player(ball) != *player {
+            return COURT_SIZE.1 as f64 / 2.0;
+        }
+        match predict_intercept(ball, paddle_face(paddle, player), arena, config) {
+            Some(intercept) => intercept.position[1] + rng.gen_range(-aim_error..=aim_error),
+            None => COURT_SIZE.1 as f64 / 2.0,
+        }
+    }
+
+    /// The input of a linear controller, the direction of the weighted sum of the features.
+    /// The features are seen from the side of the player, so the same weights play on both sides.
+    fn linear_input(
+        weights: &[f64; LINEAR_FEATURES],
+        player: &PlayerId,
+        paddle: &Paddle,
+        ball: &Ball,
+        config: &Config,
+    ) -> PaddleInput {
+        let (width, height) = (COURT_SIZE.0 as f64, COURT_SIZE.1 as f64);
+        let towards = match player {
+            PlayerId::One => -1.0,
+            PlayerId::Two => 1.0,
+        };
+        let features = [
+            1.0,
+            (ball.y + BALL_SIZE.1 as f64 / 2.0 - (paddle.y() + PADDLE_SIZE.1 as f64 / 2.0))
+                / height,
+            ball.y_velocity / config.ball.max_speed,
+            ball.x_velocity * towards / config.ball.max_speed,
+            (ball.x + BALL_SIZE.0 as f64 / 2.0 - paddle_face(paddle, player)).abs() / width,
+            paddle.velocity() / config.paddle.max_speed,
+        ];
+        let decision: f64 = weights
+            .iter()
+            .zip(features)
+            .map(|(weight, feature)| weight * feature)
+            .sum();
+
+        if decision.abs() <= AI_LINEAR_DEAD_ZONE {
+            PaddleInput::None
+        } else if decision > 0.0 {
+            PaddleInput::Up
+        } else {
+            PaddleInput::Down
+        }
+    }
+}
+
+/// The point where the ball reaches a paddle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Intercept {
+    /// The center of the ball when it reaches the paddle.
+    pub position: [f64; 2],
+    /// The time in seconds until the ball reaches the paddle.
+    pub time: f64,
+}
+
+/// The paddle the ball is moving towards, player one defends the left side.
+pub fn defending_player(ball: &Ball) -> PlayerId {
+    if ball.x_velocity < 0.0 {
+        PlayerId::One
+    } else {
+        PlayerId::Two
+    }
+}
+
+/// The x position of the side of the paddle that faces the ball.
+pub fn paddle_face(paddle: &Paddle, player: &PlayerId) -> f64 {
+    match player {
+        PlayerId::One => paddle.x() + PADDLE_SIZE.0 as f64,
+        PlayerId::Two => paddle.x(),
+    }
+}
+
+/// Predicts where the ball reaches the x position by moving a copy of it with the physics of the game.
+/// Bounces off the walls and obstacles and the curve of the spin are included, the paddles are not.
+/// Returns None if the ball leaves the court before or doesn't get there in time.
+pub fn predict_intercept(
+    ball: &Ball,
+    x: f64,
+    arena: &Arena,
+    config: &BallConfig,
+) -> Option<Intercept> {
+    let (width, height) = (BALL_SIZE.0 as f64, BALL_SIZE.1 as f64);
+    let args = UpdateArgs {
+        dt: AI_PREDICTION_STEP,
+    };
+    let from_left = ball.x + width < x;
+
+    let mut ball = ball.clone();
+    let mut time = 0.0;
+    while time < AI_PREDICTION_TIME {
+        let reached = if from_left {
+            ball.x + width >= x
+        } else {
+            ball.x <= x
+        };
+        if reached {
+            return Some(Intercept {
+                position: [ball.x + width / 2.0, ball.y + height / 2.0],
+                time,
+            });
+        }
+
+        // The ball was sent back by an obstacle and went into a goal
+        if ball.x < -width || ball.x > COURT_SIZE.0 as f64 {
+            return None;
+        }
+
+        ball.update(&args, 0.0..(COURT_SIZE.1 as f64), config);
+        for obstacle in &arena.obstacles {
+            ball.bounce_off_box(obstacle);
+        }
+        time += AI_PREDICTION_STEP;
+    }
+    None
+}
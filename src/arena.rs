@@ -0,0 +1,216 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use graphics::{rectangle, Context, Graphics};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::{
+    BALL_SIZE, COURT_SIZE, GOAL_COLOR, OBSTACLE_COLOR, PADDLE_BORDER_GAP, PADDLE_SIZE,
+    SPAWN_MARKER_SIZE,
+};
+
+/// An axis aligned rectangle in world coordinates.
+/// The origin is in the bottom left corner, just like the paddles and the ball.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a rectangle spanning the two corners, no matter in which order they are given.
+    pub fn from_corners(a: [f64; 2], b: [f64; 2]) -> Self {
+        Self::new(
+            a[0].min(b[0]),
+            a[1].min(b[1]),
+            (a[0] - b[0]).abs(),
+            (a[1] - b[1]).abs(),
+        )
+    }
+
+    pub fn contains(&self, point: [f64; 2]) -> bool {
+        point[0] >= self.x
+            && point[0] <= self.x + self.width
+            && point[1] >= self.y
+            && point[1] <= self.y + self.height
+    }
+
+    pub fn is_colliding_with_box(&self, x: f64, y: f64, width: f64, height: f64) -> bool {
+        PlayState::is_box_colliding_with_box(
+            self.x,
+            self.y,
+            self.width,
+            self.height,
+            x,
+            y,
+            width,
+            height,
+        )
+    }
+
+    pub fn as_array(&self) -> [f64; 4] {
+        [self.x, self.y, self.width, self.height]
+    }
+}
+
+/// What will be spawned at a spawn point.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpawnKind {
+    /// The ball is reset to one of these after someone has scored.
+    Ball,
+    /// The starting position of the paddle of player one.
+    PlayerOne,
+    /// The starting position of the paddle of player two.
+    PlayerTwo,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spawn {
+    pub kind: SpawnKind,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Spawn {
+    /// The area of the marker that represents this spawn point in the editor.
+    pub fn marker(&self) -> Rect {
+        Rect::new(
+            self.x - SPAWN_MARKER_SIZE / 2.0,
+            self.y - SPAWN_MARKER_SIZE / 2.0,
+            SPAWN_MARKER_SIZE,
+            SPAWN_MARKER_SIZE,
+        )
+    }
+}
+
+/// A region that scores a point for `scorer` when the ball enters it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    pub rect: Rect,
+    pub scorer: PlayerId,
+}
+
+/// The layout of the playing field.
+/// An empty arena is the classic pong field where the ball only scores by leaving the window.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Arena {
+    /// Solid boxes the ball bounces off.
+    #[serde(default)]
+    pub obstacles: Vec<Rect>,
+    #[serde(default)]
+    pub spawns: Vec<Spawn>,
+    #[serde(default)]
+    pub goals: Vec<Goal>,
+}
+
+impl Arena {
+    /// Loads an arena from a json file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    /// Loads an arena from a json file, or an empty arena if the file doesn't exist yet.
+    /// Other errors are returned, so a broken file is never replaced by an empty arena.
+    pub fn load_or_default(path: &Path) -> io::Result<Self> {
+        match Self::load(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            result => result,
+        }
+    }
+
+    /// Saves the arena as a json file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = serde_json::to_string_pretty(self)?;
+        fs::write(path, file)
+    }
+
+    /// The position of the ball after a reset.
+    /// If there are multiple ball spawn points a random one is used.
+    pub fn ball_spawn<R: Rng>(&self, rng: &mut R) -> (f64, f64) {
+        let spawns: Vec<&Spawn> = self
+            .spawns
+            .iter()
+            .filter(|spawn| spawn.kind == SpawnKind::Ball)
+            .collect();
+
+        if spawns.is_empty() {
+            ((COURT_SIZE.0 as f64) / 2.0, (COURT_SIZE.1 as f64) / 2.0)
+        } else {
+            // Spawn points are the center of the ball
+            let spawn = spawns[rng.gen_range(0..spawns.len())];
+            (
+                spawn.x - (BALL_SIZE.0 as f64) / 2.0,
+                spawn.y - (BALL_SIZE.1 as f64) / 2.0,
+            )
+        }
+    }
+
+    /// The starting position of the paddle of a player.
+    pub fn paddle_spawn(&self, player: &PlayerId) -> (f64, f64) {
+        let kind = match player {
+            PlayerId::One => SpawnKind::PlayerOne,
+            PlayerId::Two => SpawnKind::PlayerTwo,
+        };
+
+        match self.spawns.iter().find(|spawn| spawn.kind == kind) {
+            // Spawn points are the center of the paddle
+            Some(spawn) => (
+                spawn.x - (PADDLE_SIZE.0 as f64) / 2.0,
+                spawn.y - (PADDLE_SIZE.1 as f64) / 2.0,
+            ),
+            None => match player {
+                PlayerId::One => (PADDLE_BORDER_GAP, (COURT_SIZE.1 as f64) / 2.0),
+                PlayerId::Two => (
+                    (COURT_SIZE.0 as f64) - PADDLE_BORDER_GAP - (PADDLE_SIZE.0 as f64),
+                    (COURT_SIZE.1 as f64) / 2.0,
+                ),
+            },
+        }
+    }
+
+    /// Returns the player that scores if the box is inside of a goal.
+    pub fn goal_scorer(&self, x: f64, y: f64, width: f64, height: f64) -> Option<PlayerId> {
+        self.goals
+            .iter()
+            .find(|goal| goal.rect.is_colliding_with_box(x, y, width, height))
+            .map(|goal| goal.scorer)
+    }
+
+    /// Renders the obstacles and goals of the arena.
+    pub fn render<G>(&self, context: &Context, graphics: &mut G)
+    where
+        G: Graphics,
+    {
+        for goal in &self.goals {
+            rectangle(
+                GOAL_COLOR,
+                goal.rect.as_array(),
+                context.transform,
+                graphics,
+            );
+        }
+        for obstacle in &self.obstacles {
+            rectangle(
+                OBSTACLE_COLOR,
+                obstacle.as_array(),
+                context.transform,
+                graphics,
+            );
+        }
+    }
+}
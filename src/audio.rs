@@ -0,0 +1,255 @@
+use std::io;
+use std::path::Path;
+
+use crate::event::{EventSubscriber, GameEvent};
+use crate::synth::{render_sequence, Envelope, Tone, Waveform};
+use crate::{AUDIO_SAMPLE_RATE, AUDIO_VOLUME, START_BALL_VELOCITY, SYNTH_HIT_FREQUENCY};
+
+/// A sound effect of the game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sound {
+    /// A paddle hit the ball, which has this speed afterwards.
+    PaddleHit {
+        speed: f64,
+    },
+    /// The ball bounced off the top or bottom or an obstacle.
+    WallBounce,
+    Score,
+    /// A second of a countdown has passed.
+    CountdownTick,
+}
+
+impl Sound {
+    /// The tones the synthesizer plays for the sound, one after another.
+    pub fn tones(&self) -> Vec<Tone> {
+        let blip = Envelope {
+            attack: 0.002,
+            decay: 0.02,
+            sustain: 0.6,
+            release: 0.04,
+        };
+
+        match self {
+            Sound::PaddleHit { speed } => {
+                // Faster returns sound higher, doubling the speed raises the pitch by half an octave
+                let frequency = SYNTH_HIT_FREQUENCY * (speed / START_BALL_VELOCITY).max(0.5).sqrt();
+                vec![Tone {
+                    waveform: Waveform::Square,
+                    frequency,
+                    end_frequency: frequency * 0.9,
+                    duration: 0.04,
+                    envelope: blip,
+                    volume: AUDIO_VOLUME,
+                }]
+            }
+            Sound::WallBounce => vec![Tone {
+                waveform: Waveform::Triangle,
+                frequency: 220.0,
+                end_frequency: 180.0,
+                duration: 0.03,
+                envelope: blip,
+                volume: AUDIO_VOLUME,
+            }],
+            // A short burst of noise followed by two rising notes
+            Sound::Score => {
+                let note = Tone {
+                    waveform: Waveform::Square,
+                    frequency: 523.25,
+                    end_frequency: 523.25,
+                    duration: 0.1,
+                    envelope: blip,
+                    volume: AUDIO_VOLUME,
+                };
+                vec![
+                    Tone {
+                        waveform: Waveform::Noise,
+                        frequency: 4000.0,
+                        end_frequency: 1000.0,
+                        duration: 0.08,
+                        envelope: blip,
+                        volume: AUDIO_VOLUME,
+                    },
+                    note,
+                    Tone {
+                        frequency: 783.99,
+                        end_frequency: 783.99,
+                        duration: 0.2,
+                        ..note
+                    },
+                ]
+            }
+            Sound::CountdownTick => vec![Tone {
+                waveform: Waveform::Triangle,
+                frequency: 880.0,
+                end_frequency: 880.0,
+                duration: 0.03,
+                envelope: blip,
+                volume: AUDIO_VOLUME,
+            }],
+        }
+    }
+
+    /// The sound that is played for an event, if any.
+    pub fn for_event(event: &GameEvent) -> Option<Sound> {
+        match event {
+            GameEvent::PaddleHit { speed, .. } => Some(Sound::PaddleHit { speed: *speed }),
+            GameEvent::WallBounce => Some(Sound::WallBounce),
+            GameEvent::Scored { .. } => Some(Sound::Score),
+            GameEvent::CountdownTick => Some(Sound::CountdownTick),
+            GameEvent::MatchStarted { .. }
+            | GameEvent::StateChanged { .. }
+            | GameEvent::MatchOver(_) => None,
+        }
+    }
+
+    /// The samples of the sound at [`AUDIO_SAMPLE_RATE`].
+    pub fn samples(&self) -> Vec<f32> {
+        render_sequence(&self.tones())
+    }
+}
+
+/// Plays the sound effects, chosen by the [`crate::game_state::GameImpl`].
+pub trait AudioBackend {
+    fn play(&mut self, sound: Sound);
+
+    /// Called after every update of the game with the time that has passed in seconds.
+    fn advance(&mut self, _dt: f64) {}
+}
+
+/// Every audio backend subscribes to the events and plays their sounds.
+impl<A: AudioBackend> EventSubscriber for A {
+    fn handle(&mut self, event: &GameEvent) {
+        if let Some(sound) = Sound::for_event(event) {
+            self.play(sound);
+        }
+    }
+
+    fn advance(&mut self, dt: f64) {
+        AudioBackend::advance(self, dt);
+    }
+}
+
+/// Plays nothing, for running the game without an audio device.
+#[derive(Default)]
+pub struct NullAudio;
+
+impl AudioBackend for NullAudio {
+    fn play(&mut self, _: Sound) {}
+}
+
+/// Mixes the sounds into a recording instead of playing them.
+/// Tests use it to check which sounds were played and when, without an audio device.
+#[derive(Default)]
+pub struct WavRecorder {
+    /// The time since the recording started in seconds.
+    time: f64,
+    played: Vec<(f64, Sound)>,
+    samples: Vec<f32>,
+}
+
+impl WavRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The sounds with the time they were played at.
+    pub fn played(&self) -> &[(f64, Sound)] {
+        &self.played
+    }
+
+    /// The mixed samples of all sounds at [`AUDIO_SAMPLE_RATE`].
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Writes the recording into a mono 16 bit WAV file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        save_wav(path, &self.samples)
+    }
+}
+
+impl AudioBackend for WavRecorder {
+    fn play(&mut self, sound: Sound) {
+        self.played.push((self.time, sound));
+
+        let start = (self.time * AUDIO_SAMPLE_RATE as f64) as usize;
+        let samples = sound.samples();
+        if self.samples.len() < start + samples.len() {
+            self.samples.resize(start + samples.len(), 0.0);
+        }
+        for (mixed, sample) in self.samples[start..].iter_mut().zip(samples) {
+            *mixed += sample;
+        }
+    }
+
+    fn advance(&mut self, dt: f64) {
+        self.time += dt;
+    }
+}
+
+/// Writes the samples at [`AUDIO_SAMPLE_RATE`] into a mono 16 bit WAV file.
+/// Samples outside of -1 to 1 are clipped.
+pub fn save_wav(path: &Path, samples: &[f32]) -> io::Result<()> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: AUDIO_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec).map_err(io::Error::other)?;
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_sample(sample).map_err(io::Error::other)?;
+    }
+    writer.finalize().map_err(io::Error::other)
+}
+
+/// Plays the sounds on the default audio device.
+/// The game stays silent if there is no audio device.
+#[cfg(feature = "audio-device")]
+pub struct DeviceAudio {
+    // The stream has to be kept alive for the sounds to play
+    stream: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+}
+
+#[cfg(feature = "audio-device")]
+impl DeviceAudio {
+    pub fn new() -> Self {
+        let stream = match rodio::OutputStream::try_default() {
+            Ok(stream) => Some(stream),
+            Err(err) => {
+                eprintln!(
+                    "Unable to open the audio device, the game is silent: {}",
+                    err
+                );
+                None
+            }
+        };
+        Self { stream }
+    }
+}
+
+#[cfg(feature = "audio-device")]
+impl Default for DeviceAudio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "audio-device")]
+impl AudioBackend for DeviceAudio {
+    fn play(&mut self, sound: Sound) {
+        if let Some((_, handle)) = &self.stream {
+            let buffer = rodio::buffer::SamplesBuffer::new(1, AUDIO_SAMPLE_RATE, sound.samples());
+            // A sound that can't be played is skipped, the game goes on without it
+            handle.play_raw(buffer).ok();
+        }
+    }
+}
+
+/// The audio of the game, sounds are only played with the audio-device feature.
+#[cfg(feature = "audio-device")]
+pub type DefaultAudio = DeviceAudio;
+#[cfg(not(feature = "audio-device"))]
+pub type DefaultAudio = NullAudio;
@@ -1,49 +1,146 @@
-use graphics::{rectangle, Context, Graphics};
+use graphics::{rectangle, Context, Graphics, Transformed};
 use std::ops::Range;
 
+use crate::arena::Rect;
+use crate::config::BallConfig;
 use crate::{BALL_COLOR, BALL_SIZE};
 use piston::UpdateArgs;
+use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Ball {
     pub x: f64,
     pub x_velocity: f64,
     pub y: f64,
     pub y_velocity: f64,
+    /// The angular velocity in radians per second. Positive values spin counterclockwise.
+    pub spin: f64,
+    /// The current angle of the ball in radians, only used for rendering.
+    pub rotation: f64,
 }
 
 impl Ball {
+    pub fn new(x: f64, y: f64, x_velocity: f64, y_velocity: f64) -> Self {
+        Self {
+            x,
+            x_velocity,
+            y,
+            y_velocity,
+            spin: 0.0,
+            rotation: 0.0,
+        }
+    }
+
     /// Moves the ball with the velocity and bounces the ball of the "walls" specified using the y_range if they hit a wall.
-    pub fn update(&mut self, update_args: &UpdateArgs, mut y_range: Range<f64>) {
+    /// A spinning ball curves its path and gets a kick along the wall when bouncing.
+    /// Returns true if the ball has bounced off a wall.
+    pub fn update(
+        &mut self,
+        update_args: &UpdateArgs,
+        mut y_range: Range<f64>,
+        config: &BallConfig,
+    ) -> bool {
+        // Speed the ball up the longer the rally goes
+        if let Some(speed_up) = config.rally_speed_up {
+            self.x_velocity += self.x_velocity.signum() * speed_up * update_args.dt;
+        }
+
+        // Bend the path with the magnus effect.
+        // Only the y velocity is changed so that the spin can't turn the ball around.
+        self.y_velocity += config.magnus * self.spin * self.x_velocity * update_args.dt;
+
         // Move the ball the amount of velocity multiplied by the delta time.
         self.x += self.x_velocity * (update_args.dt as f64);
         self.y += self.y_velocity * (update_args.dt as f64);
 
+        // Rotate the ball and slowly lose the spin
+        self.rotation += self.spin * update_args.dt;
+        self.spin -= self.spin * (config.spin_damping * update_args.dt).min(1.0);
+
         // Subtract the ball height from the range so that the ball won't go off the screen
         y_range.end -= BALL_SIZE.1 as f64;
 
+        let bounced = self.y < y_range.start || self.y > y_range.end;
         if self.y < y_range.start {
             // If the y position is smaller
             // Invert the velocity so that the ball will go in the other direction
             self.y_velocity *= -1.0;
             // Calculate the distance the ball is under the range and set its position to that distances
             self.y = (self.y - y_range.start) * -1.0;
+            // The bottom of the ball grips the wall and pushes the ball against its spin
+            self.x_velocity -= self.spin * config.wall_spin_friction;
+            self.spin *= config.wall_spin_retention;
         } else if self.y > y_range.end {
             // If the y position is bigger than the upper range
             // Invert the velocity so that the ball will go in the other direction
             self.y_velocity *= -1.0;
             // Calculate the distance the ball is over the range and set its position to that distances.
             self.y = y_range.end - ((self.y - y_range.end) * -1.0);
+            // The top of the ball grips the wall and pushes the ball with its spin
+            self.x_velocity += self.spin * config.wall_spin_friction;
+            self.spin *= config.wall_spin_retention;
+        }
+
+        self.limit_speed(config.max_speed);
+        bounced
+    }
+
+    /// The length of the velocity.
+    pub fn speed(&self) -> f64 {
+        self.x_velocity.hypot(self.y_velocity)
+    }
+
+    /// Slows the ball down to the max speed without changing its direction.
+    pub fn limit_speed(&mut self, max_speed: f64) {
+        let speed = self.speed();
+        if speed > max_speed {
+            self.x_velocity *= max_speed / speed;
+            self.y_velocity *= max_speed / speed;
+        }
+    }
+
+    /// Bounces the ball off a solid box if they overlap.
+    /// The ball is pushed out on the side with the smallest overlap and its velocity on that axis is inverted.
+    /// Returns true if the ball has bounced.
+    pub fn bounce_off_box(&mut self, rect: &Rect) -> bool {
+        let (width, height) = (BALL_SIZE.0 as f64, BALL_SIZE.1 as f64);
+        if !rect.is_colliding_with_box(self.x, self.y, width, height) {
+            return false;
         }
+
+        // How far the ball is inside the box from every side
+        let left = self.x + width - rect.x;
+        let right = rect.x + rect.width - self.x;
+        let bottom = self.y + height - rect.y;
+        let top = rect.y + rect.height - self.y;
+
+        if left.min(right) < bottom.min(top) {
+            if left < right {
+                self.x = rect.x - width;
+                self.x_velocity = -self.x_velocity.abs();
+            } else {
+                self.x = rect.x + rect.width;
+                self.x_velocity = self.x_velocity.abs();
+            }
+        } else if bottom < top {
+            self.y = rect.y - height;
+            self.y_velocity = -self.y_velocity.abs();
+        } else {
+            self.y = rect.y + rect.height;
+            self.y_velocity = self.y_velocity.abs();
+        }
+        true
     }
 
-    /// Renders the ball.
+    /// Renders the ball as a square rotating around its center.
     pub fn render<G>(&self, context: &Context, graphics: &mut G) where G: Graphics {
-        let rect = [
-            self.x,
-            self.y,
-            BALL_SIZE.0 as f64,
-            BALL_SIZE.1 as f64,
-        ];
-        rectangle(BALL_COLOR, rect, context.transform, graphics);
+        let (width, height) = (BALL_SIZE.0 as f64, BALL_SIZE.1 as f64);
+        let transform = context
+            .transform
+            .trans(self.x + width / 2.0, self.y + height / 2.0)
+            .rot_rad(self.rotation);
+
+        let rect = [-width / 2.0, -height / 2.0, width, height];
+        rectangle(BALL_COLOR, rect, transform, graphics);
     }
 }
@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use piston::{Button, UpdateArgs};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::achievement::Achievements;
+use crate::ai::{Bot, Controller};
+use crate::arena::Arena;
+use crate::audio::NullAudio;
+use crate::config::Config;
+use crate::event::EventQueue;
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::game_state::{GameOptions, GameState, GameStateTrait};
+use crate::profile::ProfileStore;
+use crate::software::HeadlessGameImpl;
+use crate::{
+    BENCH_CONFIDENCE_Z, BENCH_MATCHES, BENCH_MAX_MATCH_TIME, BENCH_POINTS_TO_WIN, BENCH_STEP,
+    BUTTON_INSTANT_REPLAY_SKIP,
+};
+
+/// A computer player to compare, loaded from a file of the controller directory.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchController {
+    /// The name in the crosstable, the file name without the extension if it is missing.
+    #[serde(default)]
+    pub name: String,
+    pub controller: Controller,
+}
+
+/// Loads every `.json` file of the directory as a controller, sorted by their file names.
+/// Unlike the profiles a broken file is an error, so a benchmark never silently leaves out a controller.
+pub fn load_controllers(dir: &Path) -> io::Result<Vec<BenchController>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "json")
+    });
+    paths.sort();
+
+    let mut controllers = Vec::new();
+    for path in paths {
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        };
+        let file = fs::read_to_string(&path)?;
+        let mut controller: BenchController =
+            serde_json::from_str(&file).map_err(|err| invalid(err.to_string()))?;
+        if !controller.controller.is_valid() {
+            return Err(invalid(
+                "The settings of the controller are invalid".to_string(),
+            ));
+        }
+        if controller.name.is_empty() {
+            controller.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+        }
+        controllers.push(controller);
+    }
+    Ok(controllers)
+}
+
+/// How the controllers are compared.
+#[derive(Clone, Debug)]
+pub struct BenchSettings {
+    /// The matches between every pair of controllers.
+    pub matches: u32,
+    pub points_to_win: u32,
+    /// The seed of the first match, every match has its own seed counting up from it.
+    pub seed: u64,
+    pub arena: Arena,
+}
+
+impl Default for BenchSettings {
+    fn default() -> Self {
+        Self {
+            matches: BENCH_MATCHES,
+            points_to_win: BENCH_POINTS_TO_WIN,
+            seed: 0,
+            arena: Arena::default(),
+        }
+    }
+}
+
+/// Plays a match between two controllers without a window and returns the score of player one and two.
+/// The same seed always gives the same match. A match that takes longer than [`BENCH_MAX_MATCH_TIME`]
+/// ends with the score so far.
+pub fn play_match(
+    one: &Controller,
+    two: &Controller,
+    settings: &BenchSettings,
+    seed: u64,
+) -> (u32, u32) {
+    let mut config = Config::default();
+    config.rules.points_to_win = Some(settings.points_to_win);
+    let mut options: GameOptions<HeadlessGameImpl> = GameOptions {
+        rng: StdRng::seed_from_u64(seed),
+        config,
+        audio: NullAudio,
+        events: EventQueue::new(),
+        profiles: ProfileStore::in_memory(),
+        achievements: Achievements::default(),
+    };
+    let args = UpdateArgs { dt: BENCH_STEP };
+
+    let mut play = PlayState::with_arena(&mut options, settings.arena.clone());
+    play.set_bots([
+        Some(Bot::with_controller(one.clone())),
+        Some(Bot::with_controller(two.clone())),
+    ]);
+    let mut state = GameState::Play(Box::new(play));
+    let mut score = (0, 0);
+    let mut time = 0.0;
+    while time < BENCH_MAX_MATCH_TIME {
+        match &mut state {
+            GameState::Play(play) => {
+                score = (
+                    play.player(&PlayerId::One).score,
+                    play.player(&PlayerId::Two).score,
+                );
+                time += BENCH_STEP;
+            }
+            GameState::InstantReplay(_) => {
+                state.button_press(&Button::Keyboard(BUTTON_INSTANT_REPLAY_SKIP), &options)
+            }
+            GameState::MatchSummary(summary) => return summary.result().score,
+            _ => {}
+        }
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        // Nothing listens to the events, they are only dropped to not pile up
+        options.events.take();
+    }
+    score
+}
+
+/// The matches of a controller against an opponent.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BenchRecord {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl BenchRecord {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// The share of the points, a draw counts half.
+    pub fn score(&self) -> Option<f64> {
+        if self.games() == 0 {
+            return None;
+        }
+        Some((self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64)
+    }
+
+    /// The confidence interval of the score, see [`wilson_interval`].
+    pub fn interval(&self) -> Option<(f64, f64)> {
+        Some(wilson_interval(self.score()?, self.games()))
+    }
+
+    fn add(&mut self, other: &BenchRecord) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+/// The Wilson score interval of a share measured over a number of games,
+/// with the confidence of [`BENCH_CONFIDENCE_Z`].
+/// Unlike the normal approximation it stays between 0 and 1 and works for few games.
+pub fn wilson_interval(score: f64, games: u32) -> (f64, f64) {
+    let n = games as f64;
+    let z2 = BENCH_CONFIDENCE_Z * BENCH_CONFIDENCE_Z;
+    let center = (score + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = BENCH_CONFIDENCE_Z / (1.0 + z2 / n)
+        * (score * (1.0 - score) / n + z2 / (4.0 * n * n)).sqrt();
+    ((center - margin).max(0.0), (center + margin).min(1.0))
+}
+
+/// The results of every controller against every other one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crosstable {
+    names: Vec<String>,
+    /// The record of the row controller against the column controller.
+    records: Vec<Vec<BenchRecord>>,
+}
+
+impl Crosstable {
+    /// Plays every pair of controllers, switching sides after every match.
+    pub fn run(controllers: &[BenchController], settings: &BenchSettings) -> Self {
+        let len = controllers.len();
+        let mut crosstable = Self {
+            names: controllers
+                .iter()
+                .map(|controller| controller.name.clone())
+                .collect(),
+            records: vec![vec![BenchRecord::default(); len]; len],
+        };
+
+        let mut seed = settings.seed;
+        for a in 0..len {
+            for b in a + 1..len {
+                for game in 0..settings.matches {
+                    let (one, two) = if game % 2 == 0 { (a, b) } else { (b, a) };
+                    let score = play_match(
+                        &controllers[one].controller,
+                        &controllers[two].controller,
+                        settings,
+                        seed,
+                    );
+                    seed = seed.wrapping_add(1);
+                    crosstable.record(one, two, score);
+                }
+            }
+        }
+        crosstable
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The record of a controller against an opponent.
+    pub fn get(&self, controller: usize, opponent: usize) -> &BenchRecord {
+        &self.records[controller][opponent]
+    }
+
+    /// The record of a controller against all opponents.
+    pub fn total(&self, controller: usize) -> BenchRecord {
+        let mut total = BenchRecord::default();
+        for record in &self.records[controller] {
+            total.add(record);
+        }
+        total
+    }
+
+    /// Adds a match with the score of player one and two to the records of both.
+    fn record(&mut self, one: usize, two: usize, score: (u32, u32)) {
+        for (player, opponent, won, lost) in
+            [(one, two, score.0, score.1), (two, one, score.1, score.0)]
+        {
+            let record = &mut self.records[player][opponent];
+            match won.cmp(&lost) {
+                Ordering::Greater => record.wins += 1,
+                Ordering::Less => record.losses += 1,
+                Ordering::Equal => record.draws += 1,
+            }
+        }
+    }
+}
+
+/// The score of a record with its confidence interval, like "62% (48-74)".
+fn describe(record: &BenchRecord) -> String {
+    match (record.score(), record.interval()) {
+        (Some(score), Some((low, high))) => format!(
+            "{:.0}% ({:.0}-{:.0})",
+            score * 100.0,
+            low * 100.0,
+            high * 100.0
+        ),
+        _ => "-".to_string(),
+    }
+}
+
+/// A table with the score of every row controller against every column controller and in total.
+impl fmt::Display for Crosstable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width = self.names.iter().map(|name| name.len()).max().unwrap_or(0);
+        let column_width = self
+            .names
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0)
+            .max("100% (100-100)".len());
+
+        write!(f, "{:name_width$}", "")?;
+        for name in self.names.iter().map(String::as_str).chain(["Total"]) {
+            write!(f, "  {:>column_width$}", name)?;
+        }
+        writeln!(f)?;
+
+        for (row, name) in self.names.iter().enumerate() {
+            write!(f, "{:name_width$}", name)?;
+            for column in 0..self.names.len() {
+                let cell = if row == column {
+                    "-".to_string()
+                } else {
+                    describe(self.get(row, column))
+                };
+                write!(f, "  {:>column_width$}", cell)?;
+            }
+            writeln!(f, "  {:>column_width$}", describe(&self.total(row)))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use pong_rs::arena::Arena;
+use pong_rs::bench::{load_controllers, BenchSettings, Crosstable};
+
+/// The command line arguments of pong-bench.
+struct Arguments {
+    /// The directory with a file for every controller.
+    controllers: PathBuf,
+    settings: BenchSettings,
+}
+
+impl Arguments {
+    fn parse() -> Self {
+        let mut args = env::args().skip(1);
+        let mut paths = Vec::new();
+        let mut settings = BenchSettings::default();
+
+        let number = |args: &mut dyn Iterator<Item = String>, name: &str| -> u64 {
+            args.next()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_else(|| panic!("Missing a number after {}!", name))
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--matches" => settings.matches = number(&mut args, "--matches").max(1) as u32,
+                "--points" => settings.points_to_win = number(&mut args, "--points").max(1) as u32,
+                "--seed" => settings.seed = number(&mut args, "--seed"),
+                "--arena" => {
+                    let path = args.next().expect("Missing the path after --arena!");
+                    settings.arena =
+                        Arena::load(&PathBuf::from(path)).expect("Unable to load the arena!");
+                }
+                _ if arg.starts_with("--") => panic!("Unknown argument {}!", arg),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        match <[PathBuf; 1]>::try_from(paths) {
+            Ok([controllers]) => Self {
+                controllers,
+                settings,
+            },
+            Err(_) => panic!(
+                "Usage: pong-bench <controller directory> [--matches <n>] [--points <n>] [--seed <n>] [--arena <file>]"
+            ),
+        }
+    }
+}
+
+/// Plays every pair of computer players against each other and prints how often each one wins.
+fn main() {
+    let arguments = Arguments::parse();
+    let controllers =
+        load_controllers(&arguments.controllers).expect("Unable to load the controllers!");
+    if controllers.len() < 2 {
+        panic!(
+            "Found {} controllers in {}, at least two are needed!",
+            controllers.len(),
+            arguments.controllers.display()
+        );
+    }
+
+    let settings = &arguments.settings;
+    let start = Instant::now();
+    let crosstable = Crosstable::run(&controllers, settings);
+    print!("{}", crosstable);
+    println!(
+        "{} matches per pair to {} points from seed {}, the share of the matches won with 95% confidence intervals ({:.1}s)",
+        settings.matches,
+        settings.points_to_win,
+        settings.seed,
+        start.elapsed().as_secs_f64()
+    );
+}
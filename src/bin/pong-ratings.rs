@@ -0,0 +1,31 @@
+use std::env;
+use std::path::PathBuf;
+
+use pong_rs::profile::ProfileStore;
+
+/// Prints the ratings of all profiles and computer players, computed from the match history.
+fn main() {
+    let mut args = env::args().skip(1);
+    let profiles = match (args.next(), args.next()) {
+        (None, _) => ProfileStore::load_default(),
+        (Some(dir), None) => ProfileStore::load(&PathBuf::from(dir)),
+        _ => panic!("Usage: pong-ratings [<profile directory>]"),
+    };
+
+    let ratings = profiles.ratings();
+    println!(
+        "{:>4}  {:<16} {:>6} {:>5} {:>5}",
+        "#", "Name", "Rating", "Won", "Lost"
+    );
+    for (index, (participant, rating)) in ratings.leaderboard().iter().enumerate() {
+        println!(
+            "{:>4}  {:<16} {:>6.0} {:>5} {:>5}",
+            index + 1,
+            participant.name(),
+            rating.rating,
+            rating.wins,
+            rating.losses
+        );
+    }
+    println!("Computed from {} matches", profiles.history().len());
+}
@@ -0,0 +1,162 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use pong_rs::achievement::Achievements;
+use pong_rs::audio::NullAudio;
+use pong_rs::config::Config;
+use pong_rs::event::EventQueue;
+use pong_rs::game_state::GameOptions;
+use pong_rs::profile::ProfileStore;
+use pong_rs::replay::Replay;
+use pong_rs::software::{HeadlessGameImpl, SoftwareRenderer};
+use pong_rs::WINDOW_SIZE;
+
+/// How hard the GIF encoder tries to find the best colors, from 1 (best) to 30 (fastest).
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+/// The command line arguments of pong-render.
+struct Arguments {
+    /// The replay file recorded with `pong-rs --record`.
+    replay: PathBuf,
+    /// A GIF file, or a directory for the PNG files.
+    output: PathBuf,
+    /// Only every nth frame of the replay is rendered.
+    every: usize,
+    /// The size of the rendered frames in pixels.
+    size: (u32, u32),
+}
+
+impl Arguments {
+    fn parse() -> Self {
+        let mut args = env::args().skip(1);
+        let mut paths = Vec::new();
+        let mut every = 1;
+        let mut size = WINDOW_SIZE;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--every" => {
+                    every = args
+                        .next()
+                        .and_then(|every| every.parse().ok())
+                        .filter(|every| *every > 0)
+                        .expect("Missing a positive number after --every!");
+                }
+                "--size" => {
+                    let value = args.next().expect("Missing the size after --size!");
+                    size = value
+                        .split_once('x')
+                        .and_then(|(width, height)| {
+                            Some((width.parse().ok()?, height.parse().ok()?))
+                        })
+                        .unwrap_or_else(|| {
+                            panic!("Invalid size {}, expected <width>x<height>!", value)
+                        });
+                }
+                _ if arg.starts_with("--") => panic!("Unknown argument {}!", arg),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        match <[PathBuf; 2]>::try_from(paths) {
+            Ok([replay, output]) => Self {
+                replay,
+                output,
+                every,
+                size,
+            },
+            Err(_) => panic!(
+                "Usage: pong-render <replay> <output.gif | output directory> [--every <n>] [--size <width>x<height>]"
+            ),
+        }
+    }
+}
+
+fn main() {
+    let arguments = Arguments::parse();
+    let replay = Replay::load(&arguments.replay).expect("Unable to load the replay!");
+    let frames: Vec<usize> = (0..replay.frames.len()).step_by(arguments.every).collect();
+
+    let result = if arguments.output.extension().is_some_and(|ext| ext == "gif") {
+        render_gif(&replay, &frames, &arguments)
+    } else {
+        render_pngs(&replay, &frames, &arguments)
+    };
+    result.expect("Unable to write the frames!");
+
+    println!(
+        "Rendered {} frames into {}",
+        frames.len(),
+        arguments.output.display()
+    );
+}
+
+/// Creates the options for rendering, the randomness is never used.
+fn options() -> GameOptions<HeadlessGameImpl> {
+    GameOptions {
+        rng: StdRng::seed_from_u64(0),
+        config: Config::default(),
+        audio: NullAudio,
+        events: EventQueue::new(),
+        profiles: ProfileStore::in_memory(),
+        achievements: Achievements::default(),
+    }
+}
+
+/// Writes the frames as numbered PNG files into the output directory.
+fn render_pngs(replay: &Replay, frames: &[usize], arguments: &Arguments) -> io::Result<()> {
+    fs::create_dir_all(&arguments.output)?;
+
+    let mut options = options();
+    let mut renderer = SoftwareRenderer::new(arguments.size.0, arguments.size.1);
+    for (index, frame) in frames.iter().enumerate() {
+        renderer.render(&mut replay.state(*frame), &mut options);
+
+        let path = arguments.output.join(format!("frame_{:05}.png", index));
+        renderer.graphics.save_png(&path)?;
+    }
+    Ok(())
+}
+
+/// Writes the frames into an animated GIF that plays at the speed of the match.
+fn render_gif(replay: &Replay, frames: &[usize], arguments: &Arguments) -> io::Result<()> {
+    let (width, height) = arguments.size;
+    let (gif_width, gif_height) = (gif_size(width)?, gif_size(height)?);
+    let file = BufWriter::new(File::create(&arguments.output)?);
+    let mut encoder =
+        gif::Encoder::new(file, gif_width, gif_height, &[]).map_err(io::Error::other)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(io::Error::other)?;
+
+    // GIF delays are in hundredths of a second
+    let delay = (replay.frame_time * arguments.every as f64 * 100.0).round() as u16;
+
+    let mut options = options();
+    let mut renderer = SoftwareRenderer::new(width, height);
+    for frame in frames {
+        renderer.render(&mut replay.state(*frame), &mut options);
+
+        let mut pixels = renderer.graphics.pixels().to_vec();
+        let mut frame =
+            gif::Frame::from_rgba_speed(gif_width, gif_height, &mut pixels, GIF_QUANTIZE_SPEED);
+        frame.delay = delay.max(1);
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// The size of a GIF in pixels, which is limited to 65535.
+fn gif_size(size: u32) -> io::Result<u16> {
+    u16::try_from(size).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The size {} is too large for a GIF", size),
+        )
+    })
+}
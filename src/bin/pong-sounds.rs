@@ -0,0 +1,37 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use pong_rs::audio::{save_wav, Sound};
+
+/// The ball speeds the paddle hits are rendered at.
+const HIT_SPEEDS: [f64; 4] = [100.0, 200.0, 400.0, 800.0];
+
+/// Renders every sound effect of the game into a WAV file, for listening to them without playing.
+fn main() {
+    let mut args = env::args().skip(1);
+    let directory = match (args.next(), args.next()) {
+        (Some(directory), None) => PathBuf::from(directory),
+        _ => panic!("Usage: pong-sounds <output directory>"),
+    };
+    fs::create_dir_all(&directory).expect("Unable to create the output directory!");
+
+    let mut sounds = vec![
+        ("wall_bounce".to_string(), Sound::WallBounce),
+        ("score".to_string(), Sound::Score),
+        ("countdown_tick".to_string(), Sound::CountdownTick),
+    ];
+    for speed in HIT_SPEEDS {
+        sounds.push((format!("paddle_hit_{}", speed), Sound::PaddleHit { speed }));
+    }
+
+    for (name, sound) in &sounds {
+        let path = directory.join(format!("{}.wav", name));
+        save_wav(&path, &sound.samples()).expect("Unable to write the sound!");
+    }
+    println!(
+        "Rendered {} sounds into {}",
+        sounds.len(),
+        directory.display()
+    );
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Stdout, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{Hide, Show};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use piston::{Button, Key, UpdateArgs};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use pong_rs::achievement::Achievements;
+use pong_rs::arena::Arena;
+use pong_rs::audio::NullAudio;
+use pong_rs::config::Config;
+use pong_rs::event::EventQueue;
+use pong_rs::game_state::countdown::CountdownState;
+use pong_rs::game_state::play::{PlayState, PlayerId};
+use pong_rs::game_state::{GameOptions, GameState, GameStateTrait, Invalid};
+use pong_rs::profile::ProfileStore;
+use pong_rs::software::HeadlessGameImpl;
+use pong_rs::terminal::TerminalCanvas;
+
+/// The time between two updates of the game in seconds, the same as in the window.
+const UPDATE_TIME: f64 = 1.0 / 120.0;
+/// The time between two frames drawn into the terminal.
+const FRAME_TIME: Duration = Duration::from_millis(33);
+/// Most terminals only report key presses, which repeat while a key is held.
+/// A key counts as released if it wasn't repeated for this long,
+/// which has to be longer than the delay before the keyboard starts repeating.
+const KEY_RELEASE_DELAY: Duration = Duration::from_millis(600);
+
+/// Maps the keys of the terminal to the keys used by the game.
+fn to_key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Char('w' | 'W') => Some(Key::W),
+        KeyCode::Char('s' | 'S') => Some(Key::S),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Char(' ') => Some(Key::Space),
+        _ => None,
+    }
+}
+
+fn is_quit(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => true,
+        // Raw mode turns off the usual handling of ctrl+c
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+/// Plays a versus match in the terminal.
+struct TerminalGame {
+    options: GameOptions<HeadlessGameImpl>,
+    state: GameState,
+    /// The keys that are held down with the time they were last reported.
+    held: HashMap<Key, Instant>,
+    /// Whether the terminal reports key releases.
+    reports_releases: bool,
+}
+
+impl TerminalGame {
+    /// Handles the pending input, returns false if the game should quit.
+    fn handle_input(&mut self) -> io::Result<bool> {
+        while event::poll(Duration::ZERO)? {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                _ => continue,
+            };
+            if is_quit(&key) {
+                return Ok(false);
+            }
+
+            let button_key = match to_key(key.code) {
+                Some(button_key) => button_key,
+                None => continue,
+            };
+            let button = Button::Keyboard(button_key);
+            match key.kind {
+                KeyEventKind::Press | KeyEventKind::Repeat => {
+                    // Repeated presses would mess up the input of the paddle
+                    if self.held.insert(button_key, Instant::now()).is_none() {
+                        self.state.button_press(&button, &self.options);
+                    }
+                }
+                KeyEventKind::Release => {
+                    self.held.remove(&button_key);
+                    self.state.button_release(&button, &self.options);
+                }
+            }
+        }
+
+        if !self.reports_releases {
+            let state = &mut self.state;
+            let options = &self.options;
+            self.held.retain(|key, last_reported| {
+                let released = last_reported.elapsed() > KEY_RELEASE_DELAY;
+                if released {
+                    state.button_release(&Button::Keyboard(*key), options);
+                }
+                !released
+            });
+        }
+        Ok(true)
+    }
+
+    fn update(&mut self, args: &UpdateArgs) {
+        let state = mem::replace(&mut self.state, GameState::Invalid(Box::new(Invalid)));
+        self.state = match state.update(args, &mut self.options) {
+            Ok(state) | Err(state) => state,
+        };
+
+        let GameOptions {
+            events,
+            audio,
+            profiles,
+            achievements,
+            ..
+        } = &mut self.options;
+        events.dispatch(args.dt, &mut [audio, profiles, achievements]);
+        achievements.unlock_reached(profiles);
+    }
+
+    fn run(&mut self, out: &mut Stdout) -> io::Result<()> {
+        let args = UpdateArgs { dt: UPDATE_TIME };
+        let mut last_update = Instant::now();
+
+        // The match is over once the summary is shown
+        while self.handle_input()? && !matches!(self.state, GameState::MatchSummary(_)) {
+            // Catch up with the updates of the time since the last frame
+            while last_update.elapsed().as_secs_f64() >= UPDATE_TIME {
+                self.update(&args);
+                last_update += Duration::from_secs_f64(UPDATE_TIME);
+            }
+
+            let (columns, rows) = terminal::size()?;
+            let mut canvas = TerminalCanvas::new(columns, rows);
+            canvas.render(&self.state);
+            canvas.draw(out)?;
+
+            sleep(FRAME_TIME);
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut arena = Arena::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--arena" => {
+                let path = args.next().expect("Missing the path after --arena!");
+                arena = Arena::load(&PathBuf::from(path)).expect("Unable to load the arena!");
+            }
+            _ => panic!("Unknown argument {}!", arg),
+        }
+    }
+
+    let mut options: GameOptions<HeadlessGameImpl> = GameOptions {
+        rng: StdRng::from_entropy(),
+        config: Config::load_or_default(),
+        audio: NullAudio,
+        events: EventQueue::new(),
+        profiles: ProfileStore::in_memory(),
+        achievements: Achievements::default(),
+    };
+    let state = GameState::Countdown(Box::new(CountdownState::new(
+        Duration::from_secs(3),
+        GameState::Play(Box::new(PlayState::with_arena(&mut options, arena))),
+    )));
+
+    let mut out = io::stdout();
+    terminal::enable_raw_mode().expect("Unable to set up the terminal!");
+    execute!(out, EnterAlternateScreen, Hide).expect("Unable to set up the terminal!");
+    let reports_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if reports_releases {
+        execute!(
+            out,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )
+        .expect("Unable to set up the terminal!");
+    }
+
+    let mut game = TerminalGame {
+        options,
+        state,
+        held: HashMap::new(),
+        reports_releases,
+    };
+    let result = game.run(&mut out);
+
+    // Always restore the terminal, even if the game failed
+    if reports_releases {
+        execute!(out, PopKeyboardEnhancementFlags).ok();
+    }
+    execute!(out, Show, LeaveAlternateScreen).ok();
+    terminal::disable_raw_mode().ok();
+    out.flush().ok();
+
+    result.expect("Unable to draw into the terminal!");
+    if let GameState::MatchSummary(summary) = &game.state {
+        println!("{}", summary.title());
+        println!(
+            "{:<14}{:>12}{:>12}",
+            "",
+            summary.name(&PlayerId::One),
+            summary.name(&PlayerId::Two)
+        );
+        for (name, one, two) in summary.stats().rows() {
+            println!("{:<14}{:>12}{:>12}", name, one, two);
+        }
+        println!("{}", summary.stats().rally_summary());
+    }
+}
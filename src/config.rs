@@ -0,0 +1,253 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BALL_MAGNUS, BALL_MAX_SPEED, BALL_MULTIPLIER, BALL_SPIN_DAMPING, BALL_SPIN_TRANSFER,
+    BALL_WALL_SPIN_FRICTION, BALL_WALL_SPIN_RETENTION, MAX_FPS, PADDLE_ACCELERATION,
+    PADDLE_FRICTION, PADDLE_MOMENTUM_TRANSFER, PADDLE_SPEED,
+};
+
+/// The name of the config file inside of the config directory of the user.
+const CONFIG_FILE: &str = "pong-rs/config.json";
+
+/// Settings of the game that can be changed in the config file.
+/// Missing values in the config file use their default value.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub paddle: PaddleConfig,
+    pub ball: BallConfig,
+    pub display: DisplayConfig,
+    pub rules: RulesConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaddleConfig {
+    /// The acceleration of the paddle while the up or down button is pressed.
+    pub acceleration: f64,
+    /// The deceleration of the paddle while no button is pressed.
+    pub friction: f64,
+    /// The maximum speed of the paddle.
+    pub max_speed: f64,
+    /// The fraction of the paddle velocity that is added to the ball when it is hit.
+    pub momentum_transfer: f64,
+}
+
+impl Default for PaddleConfig {
+    fn default() -> Self {
+        Self {
+            acceleration: PADDLE_ACCELERATION,
+            friction: PADDLE_FRICTION,
+            max_speed: PADDLE_SPEED,
+            momentum_transfer: PADDLE_MOMENTUM_TRANSFER,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BallConfig {
+    /// The spin the ball gets for every unit of paddle velocity when it is hit.
+    pub spin_transfer: f64,
+    /// How strong the spin bends the path of the ball.
+    pub magnus: f64,
+    /// The fraction of the spin that is lost every second.
+    pub spin_damping: f64,
+    /// The x velocity the ball gets for every unit of spin when bouncing off the top or bottom.
+    pub wall_spin_friction: f64,
+    /// The fraction of the spin that is kept after bouncing off the top or bottom.
+    pub wall_spin_retention: f64,
+    /// How the speed of the ball increases when it is hit by a paddle.
+    pub speed_curve: SpeedCurve,
+    /// The speed of the ball can never get higher than this.
+    pub max_speed: f64,
+    /// The speed the ball gains every second of a rally.
+    pub rally_speed_up: Option<f64>,
+}
+
+impl Default for BallConfig {
+    fn default() -> Self {
+        Self {
+            spin_transfer: BALL_SPIN_TRANSFER,
+            magnus: BALL_MAGNUS,
+            spin_damping: BALL_SPIN_DAMPING,
+            wall_spin_friction: BALL_WALL_SPIN_FRICTION,
+            wall_spin_retention: BALL_WALL_SPIN_RETENTION,
+            speed_curve: SpeedCurve::Exponential {
+                multiplier: BALL_MULTIPLIER,
+            },
+            max_speed: BALL_MAX_SPEED,
+            rally_speed_up: None,
+        }
+    }
+}
+
+impl BallConfig {
+    /// The speed of the ball after it was hit by a paddle, never higher than the max speed.
+    pub fn speed_after_hit(&self, speed: f64) -> f64 {
+        self.speed_curve
+            .next(speed, self.max_speed)
+            .min(self.max_speed)
+    }
+}
+
+/// How the speed of the ball changes with every hit of a paddle.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpeedCurve {
+    /// Adds the same amount of speed with every hit.
+    Linear { increase: f64 },
+    /// Multiplies the speed with every hit.
+    Exponential { multiplier: f64 },
+    /// Closes a fraction of the gap to the max speed with every hit,
+    /// so the ball speeds up fast at first and slowly approaches the max speed.
+    Capped { fraction: f64 },
+}
+
+impl SpeedCurve {
+    pub fn next(&self, speed: f64, max_speed: f64) -> f64 {
+        match self {
+            SpeedCurve::Linear { increase } => speed + increase,
+            SpeedCurve::Exponential { multiplier } => speed * multiplier,
+            SpeedCurve::Capped { fraction } => speed + (max_speed - speed) * fraction,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    Windowed,
+    /// A window without decorations covering the whole monitor.
+    Borderless,
+    /// Takes over the monitor and changes its video mode.
+    Exclusive,
+}
+
+impl DisplayMode {
+    /// Parses the name of a display mode, as used in the config file.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "windowed" => Some(DisplayMode::Windowed),
+            "borderless" => Some(DisplayMode::Borderless),
+            "exclusive" => Some(DisplayMode::Exclusive),
+            _ => None,
+        }
+    }
+
+    /// Parses the optional mode after --fullscreen.
+    /// Without a mode the window covers the screen without changing the video mode.
+    pub fn from_fullscreen_arg(name: Option<&str>) -> Option<Self> {
+        match name {
+            Some(name) => Self::from_name(name),
+            None => Some(DisplayMode::Borderless),
+        }
+    }
+}
+
+impl Display for DisplayMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayMode::Windowed => write!(f, "Windowed"),
+            DisplayMode::Borderless => write!(f, "Borderless fullscreen"),
+            DisplayMode::Exclusive => write!(f, "Exclusive fullscreen"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub mode: DisplayMode,
+    /// The display mode given on the command line, used instead of the mode for a single run.
+    /// It is never saved, so the config file keeps the mode chosen in the settings.
+    #[serde(skip)]
+    pub mode_override: Option<DisplayMode>,
+    /// Changing vsync only takes effect after a restart.
+    pub vsync: bool,
+    /// The maximum frames rendered per second.
+    pub max_fps: u64,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            mode_override: None,
+            vsync: false,
+            max_fps: MAX_FPS,
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// The display mode the game runs in, the override of the command line or the saved mode.
+    pub fn current_mode(&self) -> DisplayMode {
+        self.mode_override.unwrap_or(self.mode)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RulesConfig {
+    /// The points a player needs to win a match, None for a match without end.
+    pub points_to_win: Option<u32>,
+}
+
+impl Config {
+    /// The location of the config file.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_FILE))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = serde_json::to_string_pretty(self)?;
+        fs::write(path, file)
+    }
+
+    /// Saves the config into the config file.
+    pub fn save_default(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Unable to find the config directory",
+            )
+        })?;
+        self.save(&path)
+    }
+
+    /// Loads the config file or uses the default config if there is none.
+    /// A broken config file is reported and replaced by the default config.
+    pub fn load_or_default() -> Self {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        match Self::load(&path) {
+            Ok(config) => config,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                eprintln!(
+                    "Unable to load the config file {}, using the default config: {}",
+                    path.display(),
+                    err
+                );
+                Self::default()
+            }
+        }
+    }
+}
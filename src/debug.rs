@@ -0,0 +1,388 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::time::Instant;
+
+use graphics::{Line, Rectangle, Transformed};
+use piston::Button;
+use piston::Button::Keyboard;
+
+use crate::ai::{defending_player, paddle_face, predict_intercept};
+use crate::arena::Rect;
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::game_state::{GameImpl, GameOptions, GameState, GraphicsImpl, GraphicsOptions};
+use crate::{
+    BALL_SIZE, BUTTON_DEBUG_FASTER, BUTTON_DEBUG_OVERLAY, BUTTON_DEBUG_PAUSE, BUTTON_DEBUG_REWIND,
+    BUTTON_DEBUG_REWIND_JUMP, BUTTON_DEBUG_SLOWER, BUTTON_DEBUG_STEP, COURT_SIZE,
+    DEBUG_ARROW_HEAD_SIZE, DEBUG_HITBOX_COLOR, DEBUG_INTERCEPT_COLOR, DEBUG_LINE_RADIUS,
+    DEBUG_REWIND_JUMP, DEBUG_REWIND_SNAPSHOTS, DEBUG_TEXT_COLOR, DEBUG_TEXT_GAP, DEBUG_TEXT_SIZE,
+    DEBUG_TIME_SCALES, DEBUG_VELOCITY_COLOR, DEBUG_VELOCITY_SCALE, PADDLE_SIZE,
+};
+
+/// Counts how often something happens every second.
+struct RateCounter {
+    start: Instant,
+    count: u32,
+    rate: f64,
+}
+
+impl RateCounter {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            count: 0,
+            rate: 0.0,
+        }
+    }
+
+    /// Counts a single event, the rate is updated once every second.
+    fn count(&mut self) {
+        self.count += 1;
+
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if elapsed >= 1.0 {
+            self.rate = self.count as f64 / elapsed;
+            self.count = 0;
+            self.start = Instant::now();
+        }
+    }
+}
+
+/// Pauses, single steps and slows down or speeds up the game for debugging.
+/// The game is always updated with the same time step and only the number of updates changes,
+/// so collisions happen exactly like they would at normal speed.
+pub struct TimeControl {
+    pub paused: bool,
+    /// The index of the speed in [`DEBUG_TIME_SCALES`].
+    scale: usize,
+    /// The single updates that were requested while paused.
+    steps: u32,
+    /// The updates that are owed because of the speed but weren't done yet.
+    pending: f64,
+}
+
+impl TimeControl {
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            scale: DEBUG_TIME_SCALES
+                .iter()
+                .position(|scale| *scale == 1.0)
+                .expect("Unable to find the normal speed!"),
+            steps: 0,
+            pending: 0.0,
+        }
+    }
+
+    /// The speed of the game, 1 is the normal speed.
+    pub fn scale(&self) -> f64 {
+        DEBUG_TIME_SCALES[self.scale]
+    }
+
+    pub fn button_press(&mut self, button: &Button) {
+        match button {
+            Keyboard(BUTTON_DEBUG_PAUSE) => self.paused = !self.paused,
+            Keyboard(BUTTON_DEBUG_STEP) => {
+                self.paused = true;
+                self.steps += 1;
+            }
+            Keyboard(BUTTON_DEBUG_SLOWER) => self.scale = self.scale.saturating_sub(1),
+            Keyboard(BUTTON_DEBUG_FASTER) => {
+                self.scale = (self.scale + 1).min(DEBUG_TIME_SCALES.len() - 1)
+            }
+            _ => {}
+        }
+    }
+
+    /// The number of times the game should be updated for a single update event.
+    pub fn updates(&mut self) -> u32 {
+        if self.paused {
+            return mem::take(&mut self.steps);
+        }
+
+        // A small tolerance, so that adding up 0.1 ten times gives a whole update
+        self.pending += self.scale();
+        let updates = (self.pending + 1e-9).floor();
+        self.pending -= updates;
+        updates as u32
+    }
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps the last updates of a match, so that it can be rewound and continued from an earlier update.
+pub struct RewindBuffer {
+    snapshots: VecDeque<PlayState>,
+}
+
+impl RewindBuffer {
+    pub fn new() -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(DEBUG_REWIND_SNAPSHOTS),
+        }
+    }
+
+    /// The number of updates that can be rewound.
+    pub fn len(&self) -> usize {
+        self.snapshots.len().saturating_sub(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Keeps a snapshot of the state after it was updated.
+    /// The countdowns and replays between the points are skipped, so the rally before a point can still be rewound.
+    pub fn record(&mut self, state: &GameState) {
+        match state {
+            GameState::Play(play) => {
+                if self.snapshots.len() == DEBUG_REWIND_SNAPSHOTS {
+                    self.snapshots.pop_front();
+                }
+                self.snapshots.push_back(play.as_ref().clone());
+            }
+            // Rewinding from the replay of the winning point would end the match a second time
+            GameState::InstantReplay(replay) if replay.ends_match() => self.snapshots.clear(),
+            GameState::Countdown(_) | GameState::InstantReplay(_) => {}
+            // The match is over
+            _ => self.snapshots.clear(),
+        }
+    }
+
+    /// Goes back the number of updates, or as far as possible, and returns the match at that update.
+    /// The updates after it are dropped, because continuing from there plays them differently.
+    pub fn rewind(&mut self, updates: usize) -> Option<PlayState> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let updates = updates.min(self.len());
+        self.snapshots.truncate(self.snapshots.len() - updates);
+        self.snapshots.back().cloned()
+    }
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shows the hitboxes, the velocity of the ball and where it will reach the next paddle.
+/// The rates of the updates and frames and the current state are shown in the top left corner.
+pub struct DebugOverlay {
+    pub enabled: bool,
+    /// Enables the controls of the time and the rewinding.
+    /// They change the match, so they are off by default.
+    pub controls: bool,
+    pub time: TimeControl,
+    pub rewind: RewindBuffer,
+    updates: RateCounter,
+    frames: RateCounter,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            controls: false,
+            time: TimeControl::new(),
+            rewind: RewindBuffer::new(),
+            updates: RateCounter::new(),
+            frames: RateCounter::new(),
+        }
+    }
+
+    /// Shows or hides the overlay with F3 and handles the controls of the time if they are enabled.
+    /// Rewinding replaces the state with an earlier update of the match.
+    pub fn button_press(&mut self, button: &Button, state: &mut GameState) {
+        if let Keyboard(BUTTON_DEBUG_OVERLAY) = button {
+            self.enabled = !self.enabled;
+        }
+        if !self.controls {
+            return;
+        }
+
+        let rewind = match button {
+            Keyboard(BUTTON_DEBUG_REWIND) => Some(1),
+            Keyboard(BUTTON_DEBUG_REWIND_JUMP) => Some(DEBUG_REWIND_JUMP),
+            _ => None,
+        };
+        if let Some(snapshot) = rewind.and_then(|updates| self.rewind.rewind(updates)) {
+            *state = GameState::Play(Box::new(snapshot));
+            self.time.paused = true;
+        }
+
+        self.time.button_press(button);
+    }
+
+    /// Counts an update of the game and keeps it for rewinding, called even while the overlay is hidden.
+    /// Without the controls nothing is kept, because the match can't be rewound.
+    pub fn update(&mut self, state: &GameState) {
+        self.updates.count();
+        if self.controls {
+            self.rewind.record(state);
+        }
+    }
+
+    /// Counts a rendered frame, called even while the overlay is hidden.
+    pub fn count_frame(&mut self) {
+        self.frames.count();
+    }
+
+    /// Draws the overlay over the state.
+    /// The transform of the context has to be the one of the court, like after [`crate::render_court`].
+    pub fn render<Impl: GameImpl>(
+        &self,
+        state: &GameState,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        options: &GameOptions<Impl>,
+    ) {
+        // A paused game looks like it froze, so this is shown even without the overlay
+        if self.time.paused {
+            Self::render_text::<Impl>(
+                ctx,
+                "Paused - F5 to continue, F6 for the next update, F9 / F10 to rewind",
+                (COURT_SIZE.1 as f64) - DEBUG_TEXT_GAP,
+            );
+        }
+
+        if !self.enabled {
+            return;
+        }
+
+        let mut lines = vec![format!(
+            "{}   {:.0} ticks/s   {:.0} fps   speed x{}",
+            state.name(),
+            self.updates.rate,
+            self.frames.rate,
+            self.time.scale()
+        )];
+        if self.controls {
+            lines.push(format!("{} updates to rewind", self.rewind.len()));
+        }
+        if let GameState::Play(play) = state {
+            lines.extend(Self::render_play(play, ctx, options));
+        }
+
+        for (index, line) in lines.iter().enumerate() {
+            Self::render_text::<Impl>(ctx, line, DEBUG_TEXT_GAP * (index + 1) as f64);
+        }
+    }
+
+    /// Draws a line of text at the left side of the court.
+    fn render_text<Impl: GameImpl>(
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        text: &str,
+        y: f64,
+    ) {
+        graphics::text(
+            DEBUG_TEXT_COLOR,
+            DEBUG_TEXT_SIZE,
+            text,
+            ctx.character_cache,
+            ctx.ctx.transform.trans(DEBUG_TEXT_GAP, y),
+            ctx.graphics,
+        )
+        .expect("Unable to draw text!");
+    }
+
+    /// Draws the hitboxes, the velocity and the intercept of a match.
+    /// Returns the lines of text describing the ball.
+    fn render_play<Impl: GameImpl>(
+        play: &PlayState,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        options: &GameOptions<Impl>,
+    ) -> Vec<String> {
+        // flip the screen vertically because the origin is in the top left corner
+        let transform = ctx.ctx.transform.trans(0.0, COURT_SIZE.1 as f64).flip_v();
+        let draw_state = ctx.ctx.draw_state;
+        let border = Rectangle::new_border(DEBUG_HITBOX_COLOR, DEBUG_LINE_RADIUS);
+        let outline =
+            |rect: [f64; 4], graphics: &mut <Impl::GraphicsImpl as GraphicsImpl>::Graphics| {
+                border.draw(rect, &draw_state, transform, graphics);
+            };
+
+        // The boxes used for the collisions
+        let arena = play.arena();
+        for rect in arena
+            .obstacles
+            .iter()
+            .chain(arena.goals.iter().map(|goal| &goal.rect))
+        {
+            outline(rect.as_array(), ctx.graphics);
+        }
+        for player in [play.player(&PlayerId::One), play.player(&PlayerId::Two)] {
+            let paddle = &player.paddle;
+            let rect = Rect::new(
+                paddle.x(),
+                paddle.y(),
+                PADDLE_SIZE.0 as f64,
+                PADDLE_SIZE.1 as f64,
+            );
+            outline(rect.as_array(), ctx.graphics);
+        }
+        let (ball_width, ball_height) = (BALL_SIZE.0 as f64, BALL_SIZE.1 as f64);
+        let ball = play.ball();
+        outline([ball.x, ball.y, ball_width, ball_height], ctx.graphics);
+
+        // The distance the ball moves in the next moment
+        let center = [ball.x + ball_width / 2.0, ball.y + ball_height / 2.0];
+        let velocity = [
+            center[0],
+            center[1],
+            center[0] + ball.x_velocity * DEBUG_VELOCITY_SCALE,
+            center[1] + ball.y_velocity * DEBUG_VELOCITY_SCALE,
+        ];
+        Line::new(DEBUG_VELOCITY_COLOR, DEBUG_LINE_RADIUS).draw_arrow(
+            velocity,
+            DEBUG_ARROW_HEAD_SIZE,
+            &draw_state,
+            transform,
+            ctx.graphics,
+        );
+
+        // Where the ball will be when it reaches the paddle it moves towards
+        let player = defending_player(ball);
+        let paddle = &play.player(&player).paddle;
+        let intercept = predict_intercept(
+            ball,
+            paddle_face(paddle, &player),
+            arena,
+            &options.config.ball,
+        );
+        let intercept_text = match intercept {
+            Some(intercept) => {
+                let [x, y] = intercept.position;
+                let rect = [
+                    x - ball_width / 2.0,
+                    y - ball_height / 2.0,
+                    ball_width,
+                    ball_height,
+                ];
+                Rectangle::new_border(DEBUG_INTERCEPT_COLOR, DEBUG_LINE_RADIUS).draw(
+                    rect,
+                    &draw_state,
+                    transform,
+                    ctx.graphics,
+                );
+                format!("intercept at y {:.0} in {:.2}s", y, intercept.time)
+            }
+            None => "no intercept".to_string(),
+        };
+
+        vec![
+            format!("ball speed {:.0}   spin {:.2}", ball.speed(), ball.spin),
+            intercept_text,
+        ]
+    }
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,29 @@
+use glutin::window::Fullscreen;
+use glutin_window::GlutinWindow;
+
+use crate::config::DisplayMode;
+
+/// Switches the window between windowed, borderless fullscreen and exclusive fullscreen.
+pub fn set_display_mode(window: &GlutinWindow, mode: DisplayMode) {
+    let window = window.ctx.window();
+
+    let fullscreen = match mode {
+        DisplayMode::Windowed => None,
+        DisplayMode::Borderless => Some(Fullscreen::Borderless(window.current_monitor())),
+        DisplayMode::Exclusive => {
+            // Use the biggest video mode with the highest refresh rate of the current monitor
+            let video_mode = window.current_monitor().and_then(|monitor| {
+                monitor.video_modes().max_by_key(|mode| {
+                    (mode.size().width * mode.size().height, mode.refresh_rate())
+                })
+            });
+
+            match video_mode {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                None => Some(Fullscreen::Borderless(None)),
+            }
+        }
+    };
+
+    window.set_fullscreen(fullscreen);
+}
@@ -0,0 +1,90 @@
+use std::mem;
+
+use crate::game_state::play::PlayerId;
+use crate::profile::{MatchResult, Participant};
+
+/// Something that happened during an update of the game.
+/// The states only report what happened, the reactions like sounds, statistics or achievements
+/// are up to the subscribers of the events.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A match has started between the players.
+    MatchStarted { players: [Participant; 2] },
+    /// A paddle hit the ball, which has this speed afterwards.
+    PaddleHit {
+        player: PlayerId,
+        speed: f64,
+        /// The max speed of the ball in the config of the match.
+        max_speed: f64,
+    },
+    /// The ball bounced off the top or bottom, a wall or an obstacle.
+    WallBounce,
+    Scored {
+        player: PlayerId,
+        /// The score of player one and two with the point.
+        score: (u32, u32),
+    },
+    /// A second of a countdown has passed.
+    CountdownTick,
+    /// The game switched to another state, with the names of the states.
+    StateChanged {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// A player has reached the points to win the match.
+    MatchOver(MatchResult),
+}
+
+/// Collects the events of the updates until they are dispatched.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    events: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// The events that were not dispatched yet, oldest first.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Removes all events from the queue, oldest first.
+    pub fn take(&mut self) -> Vec<GameEvent> {
+        mem::take(&mut self.events)
+    }
+
+    /// Hands every queued event to the subscribers in their order and then advances them by the time of the update.
+    /// Should be called after every update, returns the events that were dispatched.
+    pub fn dispatch(
+        &mut self,
+        dt: f64,
+        subscribers: &mut [&mut dyn EventSubscriber],
+    ) -> Vec<GameEvent> {
+        let events = self.take();
+        for event in &events {
+            for subscriber in subscribers.iter_mut() {
+                subscriber.handle(event);
+            }
+        }
+        for subscriber in subscribers.iter_mut() {
+            subscriber.advance(dt);
+        }
+        events
+    }
+}
+
+/// Reacts to the events of the game, like the audio, the profiles and the achievements.
+/// The subscribers are registered when the events are dispatched, see [`EventQueue::dispatch`].
+pub trait EventSubscriber {
+    fn handle(&mut self, event: &GameEvent);
+
+    /// Called after the events of every update with the time that has passed in seconds.
+    fn advance(&mut self, _dt: f64) {}
+}
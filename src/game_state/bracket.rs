@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+
+use crate::game_state::countdown::CountdownState;
+use crate::game_state::menu::MenuState;
+use crate::game_state::play::PlayState;
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::tournament::{Stage, Tournament, TournamentMatch};
+use crate::{
+    BUTTON_MENU_BACK, BUTTON_MENU_SELECT, COURT_SIZE, MENU_COLOR, MENU_SELECTED_COLOR,
+    MENU_TITLE_SIZE, SCORE_Y_GAP, TOURNAMENT_COLUMNS, TOURNAMENT_ELIMINATED_COLOR,
+    TOURNAMENT_POINTS_TO_WIN, TOURNAMENT_ROW_GAP, TOURNAMENT_TEXT_SIZE,
+};
+
+/// Shows the matches of the current round and the standings between the matches of a tournament.
+pub struct BracketState {
+    tournament: Tournament,
+    next: bool,
+    back: bool,
+}
+
+impl BracketState {
+    pub fn new(tournament: Tournament) -> Self {
+        Self {
+            tournament,
+            next: false,
+            back: false,
+        }
+    }
+
+    pub fn tournament(&self) -> &Tournament {
+        &self.tournament
+    }
+
+    /// The headline, like "Single elimination: Round 2" or the champion once it is over.
+    pub fn title(&self) -> String {
+        if let Some(champion) = self.tournament.champion() {
+            return format!("{} wins the tournament", self.tournament.name(champion));
+        }
+        let stage = match self.tournament.next_match() {
+            Some(TournamentMatch {
+                stage: Stage::GrandFinal,
+                ..
+            }) => "Grand final".to_string(),
+            _ => format!("Round {}", self.tournament.round()),
+        };
+        format!("{}: {}", self.tournament.format().name(), stage)
+    }
+
+    /// A match of the round, like "Alice 11:7 Bob" once it has been played.
+    fn describe(&self, game: &TournamentMatch) -> String {
+        let [one, two] = game.players.map(|entrant| self.tournament.name(entrant));
+        let versus = match game.result {
+            Some((_, (score_one, score_two))) => format!("{}:{}", score_one, score_two),
+            None => "vs".to_string(),
+        };
+        match game.stage {
+            Stage::Losers => format!("{} {} {} (losers)", one, versus, two),
+            _ => format!("{} {} {}", one, versus, two),
+        }
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for BracketState {
+    fn update(
+        mut self,
+        _: &UpdateArgs,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        if self.back {
+            return Err(GameState::Menu(Box::default()));
+        }
+        if !self.next {
+            return Ok(self);
+        }
+        self.next = false;
+
+        let Some(game) = self.tournament.next_match() else {
+            return Err(GameState::Menu(Box::default()));
+        };
+        let participants = game
+            .players
+            .map(|entrant| self.tournament.entrants()[entrant].clone());
+        let mut play = PlayState::new(options);
+        play.set_participants(participants);
+        play.set_points_to_win(
+            options
+                .config
+                .rules
+                .points_to_win
+                .unwrap_or(TOURNAMENT_POINTS_TO_WIN),
+        );
+        play.set_tournament(self.tournament);
+        Err(GameState::Countdown(Box::new(CountdownState::new(
+            Duration::from_secs(3),
+            GameState::Play(Box::new(play)),
+        ))))
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        _: &mut GameOptions<Impl>,
+    ) {
+        let [matches_x, standings_x] = TOURNAMENT_COLUMNS;
+        let top = SCORE_Y_GAP * 2.0;
+        MenuState::render_text(
+            ctx,
+            MENU_COLOR,
+            MENU_TITLE_SIZE,
+            &self.title(),
+            matches_x,
+            SCORE_Y_GAP,
+        );
+
+        let round = self.tournament.round();
+        let next = self.tournament.next_match();
+        let mut y = top;
+        for game in self
+            .tournament
+            .matches()
+            .iter()
+            .filter(|game| game.round == round)
+        {
+            y += TOURNAMENT_ROW_GAP;
+            let (color, text) = if Some(game) == next {
+                (MENU_SELECTED_COLOR, format!("> {}", self.describe(game)))
+            } else {
+                (MENU_COLOR, format!("  {}", self.describe(game)))
+            };
+            MenuState::render_text(ctx, color, TOURNAMENT_TEXT_SIZE, &text, matches_x, y);
+        }
+
+        let mut y = top;
+        for (rank, entrant) in self.tournament.ranking().into_iter().enumerate() {
+            y += TOURNAMENT_ROW_GAP;
+            let standing = self.tournament.standing(entrant);
+            let color = if self.tournament.is_eliminated(entrant) {
+                TOURNAMENT_ELIMINATED_COLOR
+            } else {
+                MENU_COLOR
+            };
+            let text = format!(
+                "{}. {} {}-{}",
+                rank + 1,
+                self.tournament.name(entrant),
+                standing.wins,
+                standing.losses
+            );
+            MenuState::render_text(ctx, color, TOURNAMENT_TEXT_SIZE, &text, standings_x, y);
+        }
+
+        let hint = if self.tournament.is_over() {
+            "Press enter to return"
+        } else {
+            "Press enter to play the next match, backspace to leave"
+        };
+        MenuState::render_text(
+            ctx,
+            MENU_COLOR,
+            TOURNAMENT_TEXT_SIZE,
+            hint,
+            matches_x,
+            (COURT_SIZE.1 as f64) - SCORE_Y_GAP / 2.0,
+        );
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        match button {
+            Keyboard(BUTTON_MENU_SELECT) => self.next = true,
+            Keyboard(BUTTON_MENU_BACK) => self.back = true,
+            _ => {}
+        }
+    }
+
+    fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use graphics::{rectangle, Transformed};
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+use rand::Rng;
+
+use crate::arena::Rect;
+use crate::ball::Ball;
+use crate::event::GameEvent;
+use crate::game_state::countdown::CountdownState;
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::paddle::{Paddle, PaddleInput};
+use crate::{
+    BALL_SIZE, BREAKOUT_BRICK_COLOR, BREAKOUT_BRICK_GAP, BREAKOUT_BRICK_SCORE, BREAKOUT_BRICK_SIZE,
+    BREAKOUT_LEVEL_SPEED_UP, BREAKOUT_LIVES, BREAKOUT_MAX_COLUMNS, BREAKOUT_START_COLUMNS,
+    BUTTON_BREAKOUT_RESTART, BUTTON_PLAYER_1_DOWN, BUTTON_PLAYER_1_UP, BUTTON_PLAYER_2_DOWN,
+    BUTTON_PLAYER_2_UP, COURT_SIZE, PADDLE_BORDER_GAP, SCORE_COLOR, SCORE_COUNTDOWN, SCORE_SIZE,
+    SCORE_Y_GAP, START_BALL_VELOCITY,
+};
+
+/// A single player practice mode.
+/// The paddle defends the left side while the ball breaks the bricks on the right side.
+pub struct BreakoutState {
+    paddle: Paddle,
+    ball: Ball,
+    bricks: Vec<Rect>,
+    lives: u32,
+    score: u32,
+    level: u32,
+    game_over: bool,
+    restart: bool,
+}
+
+impl BreakoutState {
+    pub fn new<Impl: GameImpl>(options: &mut GameOptions<Impl>) -> Self {
+        let mut state = Self {
+            paddle: Paddle::new(PADDLE_BORDER_GAP, (COURT_SIZE.1 as f64) / 2.0),
+            ball: Ball::new(0.0, 0.0, 0.0, 0.0),
+            bricks: Self::create_bricks(1),
+            lives: BREAKOUT_LIVES,
+            score: 0,
+            level: 1,
+            game_over: false,
+            restart: false,
+        };
+        state.reset_ball(options);
+        state
+    }
+
+    /// Sets the input of the paddle, both players control it.
+    pub fn set_paddle_input(&mut self, input: PaddleInput) {
+        *self.paddle.input_mut() = input;
+    }
+
+    /// Replaces the ball, like to practice a certain shot.
+    pub fn set_ball(&mut self, ball: Ball) {
+        self.ball = ball;
+    }
+
+    pub fn bricks(&self) -> &[Rect] {
+        &self.bricks
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    /// Creates the wall of bricks for a level.
+    /// Every level adds another column of bricks.
+    fn create_bricks(level: u32) -> Vec<Rect> {
+        let columns = (BREAKOUT_START_COLUMNS + level - 1).min(BREAKOUT_MAX_COLUMNS);
+        let rows = ((COURT_SIZE.1 as f64) / (BREAKOUT_BRICK_SIZE.1 + BREAKOUT_BRICK_GAP)) as u32;
+
+        // Center the rows vertically
+        let wall_height = rows as f64 * (BREAKOUT_BRICK_SIZE.1 + BREAKOUT_BRICK_GAP);
+        let bottom = ((COURT_SIZE.1 as f64) - wall_height + BREAKOUT_BRICK_GAP) / 2.0;
+
+        let mut bricks = Vec::new();
+        for column in 0..columns {
+            let x = (COURT_SIZE.0 as f64)
+                - PADDLE_BORDER_GAP
+                - (column + 1) as f64 * (BREAKOUT_BRICK_SIZE.0 + BREAKOUT_BRICK_GAP);
+            for row in 0..rows {
+                let y = bottom + row as f64 * (BREAKOUT_BRICK_SIZE.1 + BREAKOUT_BRICK_GAP);
+                bricks.push(Rect::new(
+                    x,
+                    y,
+                    BREAKOUT_BRICK_SIZE.0,
+                    BREAKOUT_BRICK_SIZE.1,
+                ));
+            }
+        }
+        bricks
+    }
+
+    /// Puts the ball back into the center and sends it towards the bricks.
+    /// The ball gets faster with every level.
+    fn reset_ball<Impl: GameImpl>(&mut self, options: &mut GameOptions<Impl>) {
+        let speed = START_BALL_VELOCITY * BREAKOUT_LEVEL_SPEED_UP.powi(self.level as i32 - 1);
+
+        self.ball.x = (COURT_SIZE.0 as f64) / 2.0;
+        self.ball.y = (COURT_SIZE.1 as f64) / 2.0;
+        self.ball.x_velocity = speed;
+        self.ball.y_velocity = options.rng.gen_range(-speed..speed);
+        self.ball.spin = 0.0;
+    }
+
+    /// Shows a countdown before continuing to play.
+    fn countdown(self) -> GameState {
+        GameState::Countdown(Box::new(CountdownState::new(
+            Duration::from_secs_f64(SCORE_COUNTDOWN),
+            GameState::Breakout(Box::new(self)),
+        )))
+    }
+
+    fn render_text<Impl: GameImpl>(
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        text: &str,
+        x: f64,
+        y: f64,
+    ) {
+        graphics::text(
+            SCORE_COLOR,
+            SCORE_SIZE,
+            text,
+            ctx.character_cache,
+            ctx.ctx.transform.trans(x, y),
+            ctx.graphics,
+        )
+        .expect("Unable to draw text!");
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for BreakoutState {
+    fn update(
+        mut self,
+        args: &UpdateArgs,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        if self.game_over {
+            if self.restart {
+                return Err(Self::new(options).countdown());
+            }
+            return Ok(self);
+        }
+
+        let y_range = 0f64..(COURT_SIZE.1 as f64);
+
+        self.paddle
+            .update(args, y_range.clone(), &options.config.paddle);
+        PlayState::check_ball_paddle_collide(&PlayerId::One, &self.paddle, &mut self.ball, options);
+
+        let mut bounced = self.ball.update(args, y_range, &options.config.ball);
+
+        // The right side is a wall
+        let right = (COURT_SIZE.0 as f64) - (BALL_SIZE.0 as f64);
+        if self.ball.x > right {
+            self.ball.x = right;
+            self.ball.x_velocity = -self.ball.x_velocity.abs();
+            bounced = true;
+        }
+        if bounced {
+            options.events.push(GameEvent::WallBounce);
+        }
+
+        // Break the first brick that was hit
+        if let Some(index) = self
+            .bricks
+            .iter()
+            .position(|brick| self.ball.bounce_off_box(brick))
+        {
+            self.bricks.remove(index);
+            self.score += BREAKOUT_BRICK_SCORE * self.level;
+
+            if self.bricks.is_empty() {
+                self.level += 1;
+                self.bricks = Self::create_bricks(self.level);
+                self.reset_ball(options);
+                return Err(self.countdown());
+            }
+        }
+
+        // The ball went past the paddle
+        if self.ball.x <= 0.0 - (BALL_SIZE.0 as f64) {
+            self.lives -= 1;
+            if self.lives == 0 {
+                self.game_over = true;
+                return Ok(self);
+            }
+
+            self.reset_ball(options);
+            return Err(self.countdown());
+        }
+
+        Ok(self)
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        _: &mut GameOptions<Impl>,
+    ) {
+        // flip the screen vertically because the origin is in the top left corner
+        let transform = ctx.ctx.transform;
+        ctx.ctx.transform = transform.trans(0.0, COURT_SIZE.1 as f64).flip_v();
+
+        for brick in &self.bricks {
+            rectangle(
+                BREAKOUT_BRICK_COLOR,
+                brick.as_array(),
+                ctx.ctx.transform,
+                ctx.graphics,
+            );
+        }
+        self.paddle.render(ctx.ctx, ctx.graphics);
+        if !self.game_over {
+            self.ball.render(ctx.ctx, ctx.graphics);
+        }
+
+        // unflip the screen
+        ctx.ctx.transform = transform;
+
+        let hud = format!(
+            "Score: {}   Lives: {}   Level: {}",
+            self.score, self.lives, self.level
+        );
+        Self::render_text::<Impl>(ctx, &hud, PADDLE_BORDER_GAP, SCORE_Y_GAP);
+
+        if self.game_over {
+            Self::render_text::<Impl>(
+                ctx,
+                "Game over! Press space to try again.",
+                (COURT_SIZE.0 as f64) / 4.0,
+                (COURT_SIZE.1 as f64) / 2.0,
+            );
+        }
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        // Both players controls move the paddle
+        if let Keyboard(BUTTON_PLAYER_1_UP | BUTTON_PLAYER_2_UP) = button {
+            self.paddle.input_mut().press_up();
+        }
+        if let Keyboard(BUTTON_PLAYER_1_DOWN | BUTTON_PLAYER_2_DOWN) = button {
+            self.paddle.input_mut().press_down();
+        }
+        if let Keyboard(BUTTON_BREAKOUT_RESTART) = button {
+            self.restart = self.game_over;
+        }
+    }
+
+    fn button_release(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if let Keyboard(BUTTON_PLAYER_1_UP | BUTTON_PLAYER_2_UP) = button {
+            self.paddle.input_mut().release_up();
+        }
+        if let Keyboard(BUTTON_PLAYER_1_DOWN | BUTTON_PLAYER_2_DOWN) = button {
+            self.paddle.input_mut().release_down();
+        }
+    }
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
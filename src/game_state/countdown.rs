@@ -1,5 +1,8 @@
+use crate::event::GameEvent;
+use crate::game_state::play::PlayerId;
 use crate::game_state::{GameImpl, GameOptions, GameStateTrait, GraphicsOptions};
-use crate::{GameState, SCORE_COUNTDOWN_COLOR, SCORE_COUNTDOWN_SIZE};
+use crate::paddle::PaddleInput;
+use crate::{GameState, COURT_SIZE, SCORE_COUNTDOWN_COLOR, SCORE_COUNTDOWN_SIZE};
 use graphics::Transformed;
 use piston::{Button, RenderArgs, UpdateArgs};
 use std::time::Duration;
@@ -15,13 +18,35 @@ impl CountdownState {
             next: Some(next),
         }
     }
+
+    /// The time until the countdown ends.
+    pub fn remaining(&self) -> Duration {
+        self.duration
+    }
+
+    /// Passes the input on to the state after the countdown, so a held stick moves the paddle once it starts.
+    pub fn set_paddle_input(&mut self, player: &PlayerId, input: PaddleInput) {
+        if let Some(next) = &mut self.next {
+            next.set_paddle_input(player, input);
+        }
+    }
 }
 impl<Impl: GameImpl> GameStateTrait<Impl> for CountdownState {
-    fn update(mut self, args: &UpdateArgs, _: &mut GameOptions<Impl>) -> Result<Self, GameState> {
+    fn update(
+        mut self,
+        args: &UpdateArgs,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        let secs = self.duration.as_secs();
         self.duration = self
             .duration
             .saturating_sub(Duration::from_secs_f64(args.dt));
 
+        // Tick whenever the shown number changes and when the countdown ends
+        if self.duration.as_secs() < secs || self.duration.is_zero() {
+            options.events.push(GameEvent::CountdownTick);
+        }
+
         if self.duration.is_zero() {
             Err(self
                 .next
@@ -35,7 +60,7 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for CountdownState {
     fn render(
         &mut self,
         ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
-        args: &RenderArgs,
+        _: &RenderArgs,
         _: &mut GameOptions<Impl>,
     ) {
         let secs = self.duration.as_secs();
@@ -44,7 +69,7 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for CountdownState {
         let transform = ctx
             .ctx
             .transform
-            .trans(args.window_size[0] / 2.0, args.window_size[1] / 2.0);
+            .trans((COURT_SIZE.0 as f64) / 2.0, (COURT_SIZE.1 as f64) / 2.0);
 
         graphics::text(
             SCORE_COUNTDOWN_COLOR,
@@ -60,4 +85,6 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for CountdownState {
     fn button_press(&mut self, _: &Button, _: &GameOptions<Impl>) {}
 
     fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
 }
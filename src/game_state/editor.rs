@@ -0,0 +1,514 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use graphics::rectangle::Rectangle;
+use graphics::types::Color;
+use graphics::{line, rectangle, Transformed};
+use piston::Button::{Keyboard, Mouse};
+use piston::{Button, MouseButton, RenderArgs, UpdateArgs};
+
+use crate::arena::{Arena, Goal, Rect, Spawn, SpawnKind};
+use crate::game_state::countdown::CountdownState;
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::{
+    BALL_COLOR, BUTTON_EDITOR_DELETE, BUTTON_EDITOR_GOAL, BUTTON_EDITOR_GRID,
+    BUTTON_EDITOR_OBSTACLE, BUTTON_EDITOR_SAVE, BUTTON_EDITOR_SPAWN, BUTTON_EDITOR_TEST_PLAY,
+    COURT_SIZE, EDITOR_BUTTON_COLOR, EDITOR_BUTTON_SIZE, EDITOR_GRID_COLOR, EDITOR_GRID_SIZE,
+    EDITOR_HANDLE_SIZE, EDITOR_SELECTION_COLOR, EDITOR_TEXT_COLOR, EDITOR_TEXT_SIZE,
+    PLAYER_ONE_COLOR, PLAYER_TWO_COLOR, SPAWN_MARKER_SIZE,
+};
+
+/// What will be placed when clicking on an empty spot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditorTool {
+    Obstacle,
+    Spawn(SpawnKind),
+    Goal(PlayerId),
+}
+
+impl EditorTool {
+    fn name(&self) -> &'static str {
+        match self {
+            EditorTool::Obstacle => "Obstacle",
+            EditorTool::Spawn(SpawnKind::Ball) => "Ball spawn",
+            EditorTool::Spawn(SpawnKind::PlayerOne) => "Player one spawn",
+            EditorTool::Spawn(SpawnKind::PlayerTwo) => "Player two spawn",
+            EditorTool::Goal(PlayerId::One) => "Goal for player one",
+            EditorTool::Goal(PlayerId::Two) => "Goal for player two",
+        }
+    }
+}
+
+/// An object of the arena, referenced by its index.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Selection {
+    Obstacle(usize),
+    Spawn(usize),
+    Goal(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Drag {
+    /// A new obstacle or goal is being drawn from this corner.
+    Create { start: [f64; 2] },
+    /// The selection follows the cursor, keeping the offset to where it was grabbed.
+    Move { offset: [f64; 2] },
+    /// The selection is resized, keeping the anchor corner in place.
+    Resize { anchor: [f64; 2] },
+}
+
+/// An editor for placing, resizing and deleting the objects of an arena with the mouse.
+#[derive(Clone)]
+pub struct EditorState {
+    arena: Arena,
+    path: PathBuf,
+    tool: EditorTool,
+    snap: bool,
+    /// The cursor position in world coordinates.
+    cursor: [f64; 2],
+    /// The cursor position with the origin in the top left corner, used for the buttons.
+    window_cursor: [f64; 2],
+    selection: Option<Selection>,
+    drag: Option<Drag>,
+    status: String,
+    test_play: bool,
+}
+
+impl EditorState {
+    pub fn new(arena: Arena, path: PathBuf) -> Self {
+        Self {
+            arena,
+            path,
+            tool: EditorTool::Obstacle,
+            snap: true,
+            cursor: [0.0, 0.0],
+            window_cursor: [0.0, 0.0],
+            selection: None,
+            drag: None,
+            status: String::new(),
+            test_play: false,
+        }
+    }
+
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    /// The area of the save button with the origin in the top left corner.
+    fn save_button() -> Rect {
+        Rect::new(
+            (COURT_SIZE.0 as f64) - 2.0 * (EDITOR_BUTTON_SIZE.0 + 10.0),
+            10.0,
+            EDITOR_BUTTON_SIZE.0,
+            EDITOR_BUTTON_SIZE.1,
+        )
+    }
+    /// The area of the test play button with the origin in the top left corner.
+    fn test_play_button() -> Rect {
+        Rect::new(
+            (COURT_SIZE.0 as f64) - (EDITOR_BUTTON_SIZE.0 + 10.0),
+            10.0,
+            EDITOR_BUTTON_SIZE.0,
+            EDITOR_BUTTON_SIZE.1,
+        )
+    }
+
+    fn snap(&self, point: [f64; 2]) -> [f64; 2] {
+        if self.snap {
+            [
+                (point[0] / EDITOR_GRID_SIZE).round() * EDITOR_GRID_SIZE,
+                (point[1] / EDITOR_GRID_SIZE).round() * EDITOR_GRID_SIZE,
+            ]
+        } else {
+            point
+        }
+    }
+
+    fn bounds(&self, selection: Selection) -> Rect {
+        match selection {
+            Selection::Obstacle(index) => self.arena.obstacles[index],
+            Selection::Spawn(index) => self.arena.spawns[index].marker(),
+            Selection::Goal(index) => self.arena.goals[index].rect,
+        }
+    }
+
+    fn bounds_mut(&mut self, selection: Selection) -> Option<&mut Rect> {
+        match selection {
+            Selection::Obstacle(index) => Some(&mut self.arena.obstacles[index]),
+            Selection::Goal(index) => Some(&mut self.arena.goals[index].rect),
+            Selection::Spawn(_) => None,
+        }
+    }
+
+    /// The resize handle in the top right corner of the selection.
+    fn handle(&self) -> Option<Rect> {
+        let selection = self.selection?;
+        if let Selection::Spawn(_) = selection {
+            return None;
+        }
+
+        let bounds = self.bounds(selection);
+        Some(Rect::new(
+            bounds.x + bounds.width - EDITOR_HANDLE_SIZE / 2.0,
+            bounds.y + bounds.height - EDITOR_HANDLE_SIZE / 2.0,
+            EDITOR_HANDLE_SIZE,
+            EDITOR_HANDLE_SIZE,
+        ))
+    }
+
+    /// Finds the object under the point, preferring the ones drawn on top.
+    fn object_at(&self, point: [f64; 2]) -> Option<Selection> {
+        let spawns = self.arena.spawns.iter().enumerate().rev();
+        let obstacles = self.arena.obstacles.iter().enumerate().rev();
+        let goals = self.arena.goals.iter().enumerate().rev();
+
+        spawns
+            .filter(|(_, spawn)| spawn.marker().contains(point))
+            .map(|(index, _)| Selection::Spawn(index))
+            .chain(
+                obstacles
+                    .filter(|(_, obstacle)| obstacle.contains(point))
+                    .map(|(index, _)| Selection::Obstacle(index)),
+            )
+            .chain(
+                goals
+                    .filter(|(_, goal)| goal.rect.contains(point))
+                    .map(|(index, _)| Selection::Goal(index)),
+            )
+            .next()
+    }
+
+    fn delete(&mut self, selection: Selection) {
+        match selection {
+            Selection::Obstacle(index) => {
+                self.arena.obstacles.remove(index);
+            }
+            Selection::Spawn(index) => {
+                self.arena.spawns.remove(index);
+            }
+            Selection::Goal(index) => {
+                self.arena.goals.remove(index);
+            }
+        }
+        self.selection = None;
+        self.drag = None;
+    }
+
+    fn place_spawn(&mut self, kind: SpawnKind, point: [f64; 2]) {
+        // Every player can only have one spawn point
+        if kind != SpawnKind::Ball {
+            self.arena.spawns.retain(|spawn| spawn.kind != kind);
+        }
+
+        self.arena.spawns.push(Spawn {
+            kind,
+            x: point[0],
+            y: point[1],
+        });
+        self.selection = Some(Selection::Spawn(self.arena.spawns.len() - 1));
+    }
+
+    fn save(&mut self) {
+        self.status = match self.arena.save(&self.path) {
+            Ok(()) => format!("Saved to {}", self.path.display()),
+            Err(err) => format!("Unable to save to {}: {}", self.path.display(), err),
+        };
+    }
+
+    fn press_left(&mut self) {
+        if Self::save_button().contains(self.window_cursor) {
+            self.save();
+            return;
+        }
+        if Self::test_play_button().contains(self.window_cursor) {
+            self.test_play = true;
+            return;
+        }
+
+        if let Some(handle) = self.handle() {
+            if handle.contains(self.cursor) {
+                let bounds = self.bounds(self.selection.unwrap());
+                self.drag = Some(Drag::Resize {
+                    anchor: [bounds.x, bounds.y],
+                });
+                return;
+            }
+        }
+
+        if let Some(selection) = self.object_at(self.cursor) {
+            let bounds = self.bounds(selection);
+            self.selection = Some(selection);
+            self.drag = Some(Drag::Move {
+                offset: [self.cursor[0] - bounds.x, self.cursor[1] - bounds.y],
+            });
+            return;
+        }
+
+        let point = self.snap(self.cursor);
+        self.selection = None;
+        match self.tool {
+            EditorTool::Obstacle | EditorTool::Goal(_) => {
+                self.drag = Some(Drag::Create { start: point })
+            }
+            EditorTool::Spawn(kind) => self.place_spawn(kind, point),
+        }
+    }
+
+    fn release_left(&mut self) {
+        if let Some(Drag::Create { start }) = self.drag {
+            let rect = Rect::from_corners(start, self.snap(self.cursor));
+
+            if rect.width > 0.0 && rect.height > 0.0 {
+                match self.tool {
+                    EditorTool::Obstacle => {
+                        self.arena.obstacles.push(rect);
+                        self.selection = Some(Selection::Obstacle(self.arena.obstacles.len() - 1));
+                    }
+                    EditorTool::Goal(scorer) => {
+                        self.arena.goals.push(Goal { rect, scorer });
+                        self.selection = Some(Selection::Goal(self.arena.goals.len() - 1));
+                    }
+                    EditorTool::Spawn(_) => {}
+                }
+            }
+        }
+        self.drag = None;
+    }
+
+    fn drag_to_cursor(&mut self) {
+        let (drag, selection) = match (self.drag, self.selection) {
+            (Some(drag), Some(selection)) => (drag, selection),
+            _ => return,
+        };
+
+        match drag {
+            Drag::Move { offset } => {
+                let corner = [self.cursor[0] - offset[0], self.cursor[1] - offset[1]];
+                match selection {
+                    Selection::Spawn(index) => {
+                        // Spawns are positioned by their center and not by their corner
+                        let center = self.snap([
+                            corner[0] + SPAWN_MARKER_SIZE / 2.0,
+                            corner[1] + SPAWN_MARKER_SIZE / 2.0,
+                        ]);
+                        let spawn = &mut self.arena.spawns[index];
+                        spawn.x = center[0];
+                        spawn.y = center[1];
+                    }
+                    _ => {
+                        let corner = self.snap(corner);
+                        if let Some(bounds) = self.bounds_mut(selection) {
+                            bounds.x = corner[0];
+                            bounds.y = corner[1];
+                        }
+                    }
+                }
+            }
+            Drag::Resize { anchor } => {
+                let corner = self.snap(self.cursor);
+                if let Some(bounds) = self.bounds_mut(selection) {
+                    *bounds = Rect::from_corners(anchor, corner);
+                }
+            }
+            Drag::Create { .. } => {}
+        }
+    }
+
+    fn render_text<Impl: GameImpl>(
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        text: &str,
+        x: f64,
+        y: f64,
+    ) {
+        graphics::text(
+            EDITOR_TEXT_COLOR,
+            EDITOR_TEXT_SIZE,
+            text,
+            ctx.character_cache,
+            ctx.ctx.transform.trans(x, y),
+            ctx.graphics,
+        )
+        .expect("Unable to draw text!");
+    }
+
+    fn render_button<Impl: GameImpl>(
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        button: Rect,
+        text: &str,
+    ) {
+        rectangle(
+            EDITOR_BUTTON_COLOR,
+            button.as_array(),
+            ctx.ctx.transform,
+            ctx.graphics,
+        );
+        Self::render_text::<Impl>(ctx, text, button.x + 10.0, button.y + button.height - 10.0);
+    }
+
+    fn spawn_color(kind: SpawnKind) -> Color {
+        match kind {
+            SpawnKind::Ball => BALL_COLOR,
+            SpawnKind::PlayerOne => PLAYER_ONE_COLOR,
+            SpawnKind::PlayerTwo => PLAYER_TWO_COLOR,
+        }
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for EditorState {
+    fn update(
+        mut self,
+        _: &UpdateArgs,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        if !self.test_play {
+            return Ok(self);
+        }
+        self.test_play = false;
+
+        let mut play = PlayState::with_arena(options, self.arena.clone());
+        play.set_editor(self);
+        Err(GameState::Countdown(Box::new(CountdownState::new(
+            Duration::from_secs(3),
+            GameState::Play(Box::new(play)),
+        ))))
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        _: &mut GameOptions<Impl>,
+    ) {
+        // flip the screen vertically because the origin is in the top left corner
+        let transform = ctx.ctx.transform;
+        ctx.ctx.transform = transform.trans(0.0, COURT_SIZE.1 as f64).flip_v();
+
+        if self.snap {
+            let (width, height) = (COURT_SIZE.0 as f64, COURT_SIZE.1 as f64);
+            let mut x = 0.0;
+            while x <= width {
+                line(
+                    EDITOR_GRID_COLOR,
+                    0.5,
+                    [x, 0.0, x, height],
+                    ctx.ctx.transform,
+                    ctx.graphics,
+                );
+                x += EDITOR_GRID_SIZE;
+            }
+            let mut y = 0.0;
+            while y <= height {
+                line(
+                    EDITOR_GRID_COLOR,
+                    0.5,
+                    [0.0, y, width, y],
+                    ctx.ctx.transform,
+                    ctx.graphics,
+                );
+                y += EDITOR_GRID_SIZE;
+            }
+        }
+
+        self.arena.render(ctx.ctx, ctx.graphics);
+        for spawn in &self.arena.spawns {
+            rectangle(
+                Self::spawn_color(spawn.kind),
+                spawn.marker().as_array(),
+                ctx.ctx.transform,
+                ctx.graphics,
+            );
+        }
+
+        if let Some(Drag::Create { start }) = self.drag {
+            let preview = Rect::from_corners(start, self.snap(self.cursor));
+            Rectangle::new_border(EDITOR_SELECTION_COLOR, 1.0).draw(
+                preview.as_array(),
+                &ctx.ctx.draw_state,
+                ctx.ctx.transform,
+                ctx.graphics,
+            );
+        }
+        if let Some(selection) = self.selection {
+            Rectangle::new_border(EDITOR_SELECTION_COLOR, 1.0).draw(
+                self.bounds(selection).as_array(),
+                &ctx.ctx.draw_state,
+                ctx.ctx.transform,
+                ctx.graphics,
+            );
+        }
+        if let Some(handle) = self.handle() {
+            rectangle(
+                EDITOR_SELECTION_COLOR,
+                handle.as_array(),
+                ctx.ctx.transform,
+                ctx.graphics,
+            );
+        }
+
+        // unflip the screen
+        ctx.ctx.transform = transform;
+
+        Self::render_button::<Impl>(ctx, Self::save_button(), "Save");
+        Self::render_button::<Impl>(ctx, Self::test_play_button(), "Test Play");
+
+        let tool = format!(
+            "Tool: {} | Grid snapping: {}",
+            self.tool.name(),
+            if self.snap { "on" } else { "off" }
+        );
+        Self::render_text::<Impl>(ctx, &tool, 10.0, 30.0);
+        Self::render_text::<Impl>(ctx, &self.status, 10.0, (COURT_SIZE.1 as f64) - 10.0);
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        match button {
+            Mouse(MouseButton::Left) => self.press_left(),
+            Mouse(MouseButton::Right) => {
+                if let Some(selection) = self.object_at(self.cursor) {
+                    self.delete(selection);
+                }
+            }
+            Keyboard(BUTTON_EDITOR_OBSTACLE) => self.tool = EditorTool::Obstacle,
+            Keyboard(BUTTON_EDITOR_SPAWN) => {
+                // Pressing the button again cycles through the spawn kinds
+                self.tool = match self.tool {
+                    EditorTool::Spawn(SpawnKind::Ball) => EditorTool::Spawn(SpawnKind::PlayerOne),
+                    EditorTool::Spawn(SpawnKind::PlayerOne) => {
+                        EditorTool::Spawn(SpawnKind::PlayerTwo)
+                    }
+                    _ => EditorTool::Spawn(SpawnKind::Ball),
+                }
+            }
+            Keyboard(BUTTON_EDITOR_GOAL) => {
+                // Pressing the button again switches the player that scores
+                self.tool = match self.tool {
+                    EditorTool::Goal(PlayerId::One) => EditorTool::Goal(PlayerId::Two),
+                    _ => EditorTool::Goal(PlayerId::One),
+                }
+            }
+            Keyboard(BUTTON_EDITOR_GRID) => self.snap = !self.snap,
+            Keyboard(BUTTON_EDITOR_DELETE) => {
+                if let Some(selection) = self.selection {
+                    self.delete(selection);
+                }
+            }
+            Keyboard(BUTTON_EDITOR_SAVE) => self.save(),
+            Keyboard(BUTTON_EDITOR_TEST_PLAY) => self.test_play = true,
+            _ => {}
+        }
+    }
+
+    fn button_release(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if let Mouse(MouseButton::Left) = button {
+            self.release_left();
+        }
+    }
+
+    fn mouse_move(&mut self, position: &[f64; 2], _: &GameOptions<Impl>) {
+        self.window_cursor = *position;
+        // The world has its origin in the bottom left corner
+        self.cursor = [position[0], (COURT_SIZE.1 as f64) - position[1]];
+        self.drag_to_cursor();
+    }
+}
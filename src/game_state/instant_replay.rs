@@ -0,0 +1,135 @@
+use graphics::types::FontSize;
+use graphics::{CharacterCache, Transformed};
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+
+use crate::arena::Arena;
+use crate::game_state::play::PlayerId;
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::paddle::PaddleInput;
+use crate::replay::ReplayFrame;
+use crate::{
+    BUTTON_INSTANT_REPLAY_SKIP, COURT_SIZE, INSTANT_REPLAY_COLOR, INSTANT_REPLAY_HINT_SIZE,
+    INSTANT_REPLAY_SPEED, INSTANT_REPLAY_TEXT_SIZE, SCORE_Y_GAP,
+};
+
+/// Shows the end of a rally again in slow motion after a point was scored.
+/// The frames are drawn exactly like the match, with a banner over them.
+pub struct InstantReplayState {
+    frames: Vec<ReplayFrame>,
+    arena: Arena,
+    /// The time between two frames when they were recorded.
+    frame_time: f64,
+    /// How far the replay has played, in seconds of the rally.
+    time: f64,
+    skip: bool,
+    next: Option<GameState>,
+}
+
+impl InstantReplayState {
+    pub fn new(frames: Vec<ReplayFrame>, arena: Arena, frame_time: f64, next: GameState) -> Self {
+        Self {
+            frames,
+            arena,
+            frame_time,
+            time: 0.0,
+            skip: false,
+            next: Some(next),
+        }
+    }
+
+    /// The frame that is shown at the current time, None after the last frame.
+    fn frame(&self) -> Option<&ReplayFrame> {
+        self.frames.get((self.time / self.frame_time) as usize)
+    }
+
+    /// The state showing the current frame, None after the last frame.
+    pub fn state(&self) -> Option<GameState> {
+        self.frame().map(|frame| frame.to_state(&self.arena))
+    }
+
+    /// Whether the replay shows the point that won the match.
+    pub fn ends_match(&self) -> bool {
+        matches!(self.next, Some(GameState::MatchSummary(_)))
+    }
+
+    /// Passes the input on to the state after the replay, the replayed paddles can't be moved.
+    pub fn set_paddle_input(&mut self, player: &PlayerId, input: PaddleInput) {
+        if let Some(next) = &mut self.next {
+            next.set_paddle_input(player, input);
+        }
+    }
+
+    /// Draws the text centered at the height.
+    fn render_centered<Impl: GameImpl>(
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        text: &str,
+        size: FontSize,
+        y: f64,
+    ) {
+        let width = ctx
+            .character_cache
+            .width(size, text)
+            .expect("Unable to measure text!");
+        let x = ((COURT_SIZE.0 as f64) - width) / 2.0;
+
+        graphics::text(
+            INSTANT_REPLAY_COLOR,
+            size,
+            text,
+            ctx.character_cache,
+            ctx.ctx.transform.trans(x, y),
+            ctx.graphics,
+        )
+        .expect("Unable to draw text!");
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for InstantReplayState {
+    fn update(mut self, args: &UpdateArgs, _: &mut GameOptions<Impl>) -> Result<Self, GameState> {
+        self.time += args.dt * INSTANT_REPLAY_SPEED;
+
+        if self.skip || self.frame().is_none() {
+            Err(self
+                .next
+                .take()
+                .expect("Unable to find the next state after the replay has ended!"))
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        args: &RenderArgs,
+        options: &mut GameOptions<Impl>,
+    ) {
+        if let Some(mut state) = self.state() {
+            state.render(ctx, args, options);
+        }
+
+        Self::render_centered::<Impl>(
+            ctx,
+            "REPLAY",
+            INSTANT_REPLAY_TEXT_SIZE,
+            SCORE_Y_GAP + INSTANT_REPLAY_TEXT_SIZE as f64,
+        );
+        Self::render_centered::<Impl>(
+            ctx,
+            "Press space to skip",
+            INSTANT_REPLAY_HINT_SIZE,
+            (COURT_SIZE.1 as f64) - SCORE_Y_GAP,
+        );
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if let Keyboard(BUTTON_INSTANT_REPLAY_SKIP) = button {
+            self.skip = true;
+        }
+    }
+
+    fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
@@ -0,0 +1,81 @@
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+
+use crate::game_state::menu::MenuState;
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::{
+    BUTTON_MENU_BACK, BUTTON_MENU_SELECT, COURT_SIZE, LEADERBOARD_COLUMNS, LEADERBOARD_ROWS,
+    MENU_COLOR, MENU_SELECTED_COLOR, MENU_TITLE_SIZE, SCORE_Y_GAP, SUMMARY_ROW_GAP,
+    SUMMARY_TEXT_SIZE,
+};
+
+/// Shows the best rated profiles and computer players.
+#[derive(Default)]
+pub struct LeaderboardState {
+    back: bool,
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for LeaderboardState {
+    fn update(self, _: &UpdateArgs, _: &mut GameOptions<Impl>) -> Result<Self, GameState> {
+        if self.back {
+            return Err(GameState::Menu(Box::default()));
+        }
+        Ok(self)
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        options: &mut GameOptions<Impl>,
+    ) {
+        let [rank_x, name_x, rating_x, wins_x, losses_x] = LEADERBOARD_COLUMNS;
+        let mut y = SCORE_Y_GAP * 2.0;
+        MenuState::render_text(ctx, MENU_COLOR, MENU_TITLE_SIZE, "Leaderboard", rank_x, y);
+
+        y += SUMMARY_ROW_GAP * 1.5;
+        for (x, text) in [
+            (name_x, "Name"),
+            (rating_x, "Rating"),
+            (wins_x, "Won"),
+            (losses_x, "Lost"),
+        ] {
+            MenuState::render_text(ctx, MENU_SELECTED_COLOR, SUMMARY_TEXT_SIZE, text, x, y);
+        }
+
+        let leaderboard = options.profiles.ratings().leaderboard();
+        for (index, (participant, rating)) in leaderboard.iter().take(LEADERBOARD_ROWS).enumerate()
+        {
+            y += SUMMARY_ROW_GAP;
+            let columns = [
+                (rank_x, format!("{}.", index + 1)),
+                (name_x, participant.name().to_string()),
+                (rating_x, format!("{:.0}", rating.rating)),
+                (wins_x, rating.wins.to_string()),
+                (losses_x, rating.losses.to_string()),
+            ];
+            for (x, text) in columns {
+                MenuState::render_text(ctx, MENU_COLOR, SUMMARY_TEXT_SIZE, &text, x, y);
+            }
+        }
+
+        MenuState::render_text(
+            ctx,
+            MENU_COLOR,
+            SUMMARY_TEXT_SIZE,
+            "Press enter to return",
+            rank_x,
+            (COURT_SIZE.1 as f64) - SCORE_Y_GAP,
+        );
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if let Keyboard(BUTTON_MENU_SELECT | BUTTON_MENU_BACK) = button {
+            self.back = true;
+        }
+    }
+
+    fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
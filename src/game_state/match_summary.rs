@@ -0,0 +1,127 @@
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+
+use crate::game_state::menu::MenuState;
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::profile::{MatchResult, Participant};
+use crate::stats::MatchStats;
+use crate::{
+    BUTTON_SUMMARY_CONTINUE, COURT_SIZE, MENU_COLOR, MENU_SELECTED_COLOR, MENU_TITLE_SIZE,
+    SCORE_Y_GAP, SUMMARY_COLUMNS, SUMMARY_ROW_GAP, SUMMARY_TEXT_SIZE,
+};
+
+/// Shows the statistics of a match after it has ended.
+pub struct MatchSummaryState {
+    stats: MatchStats,
+    result: MatchResult,
+    done: bool,
+    next: Option<GameState>,
+}
+
+impl MatchSummaryState {
+    pub fn new(stats: MatchStats, result: MatchResult, next: GameState) -> Self {
+        Self {
+            stats,
+            result,
+            done: false,
+            next: Some(next),
+        }
+    }
+
+    pub fn stats(&self) -> &MatchStats {
+        &self.stats
+    }
+
+    pub fn result(&self) -> &MatchResult {
+        &self.result
+    }
+
+    /// The name of the profile or computer player, or which player it is for a guest.
+    pub fn name(&self, player: &PlayerId) -> &str {
+        match (self.result.player(player), player) {
+            (Participant::Guest, PlayerId::One) => "Player one",
+            (Participant::Guest, PlayerId::Two) => "Player two",
+            (participant, _) => participant.name(),
+        }
+    }
+
+    /// The headline of the summary, like "Player one wins 11:7".
+    pub fn title(&self) -> String {
+        let (one, two) = self.result.score;
+        format!("{} wins {}:{}", self.name(&self.result.winner), one, two)
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for MatchSummaryState {
+    fn update(mut self, _: &UpdateArgs, _: &mut GameOptions<Impl>) -> Result<Self, GameState> {
+        if self.done {
+            return Err(self
+                .next
+                .take()
+                .expect("Unable to find the next state after the summary!"));
+        }
+        Ok(self)
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        options: &mut GameOptions<Impl>,
+    ) {
+        let [name_x, one_x, two_x] = SUMMARY_COLUMNS;
+        let mut y = SCORE_Y_GAP * 2.0;
+        MenuState::render_text(
+            ctx,
+            MENU_SELECTED_COLOR,
+            MENU_TITLE_SIZE,
+            &self.title(),
+            name_x,
+            y,
+        );
+
+        y += SUMMARY_ROW_GAP * 1.5;
+        for (x, player) in [(one_x, PlayerId::One), (two_x, PlayerId::Two)] {
+            let name = self.name(&player);
+            MenuState::render_text(ctx, MENU_SELECTED_COLOR, SUMMARY_TEXT_SIZE, name, x, y);
+        }
+        for (name, one, two) in self.stats.rows() {
+            y += SUMMARY_ROW_GAP;
+            for (x, text) in [(name_x, name), (one_x, one.as_str()), (two_x, two.as_str())] {
+                MenuState::render_text(ctx, MENU_COLOR, SUMMARY_TEXT_SIZE, text, x, y);
+            }
+        }
+
+        y += SUMMARY_ROW_GAP * 1.5;
+        MenuState::render_text(
+            ctx,
+            MENU_COLOR,
+            SUMMARY_TEXT_SIZE,
+            &self.stats.rally_summary(),
+            name_x,
+            y,
+        );
+        MenuState::render_text(
+            ctx,
+            MENU_COLOR,
+            SUMMARY_TEXT_SIZE,
+            "Press enter to continue",
+            name_x,
+            (COURT_SIZE.1 as f64) - SCORE_Y_GAP,
+        );
+        if let Some(toast) = options.achievements.toast() {
+            PlayState::render_toast(toast, ctx);
+        }
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if let Keyboard(BUTTON_SUMMARY_CONTINUE) = button {
+            self.done = true;
+        }
+    }
+
+    fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
@@ -0,0 +1,215 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use graphics::types::{Color, FontSize};
+use graphics::Transformed;
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+
+use crate::arena::Arena;
+use crate::game_state::breakout::BreakoutState;
+use crate::game_state::countdown::CountdownState;
+use crate::game_state::editor::EditorState;
+use crate::game_state::settings::SettingsState;
+use crate::game_state::{
+    GameImpl, GameOptions, GameState, GameStateTrait, GraphicsImpl, GraphicsOptions,
+};
+use crate::{
+    BUTTON_MENU_DOWN, BUTTON_MENU_SELECT, BUTTON_MENU_UP, BUTTON_PLAYER_1_DOWN, BUTTON_PLAYER_1_UP,
+    COURT_SIZE, EDITOR_DEFAULT_PATH, MENU_COLOR, MENU_ITEM_GAP, MENU_SELECTED_COLOR,
+    MENU_TEXT_SIZE, MENU_TITLE_SIZE,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MenuEntry {
+    Versus,
+    Tournament,
+    Practice,
+    Editor,
+    Leaderboard,
+    Settings,
+}
+
+impl MenuEntry {
+    const ALL: [MenuEntry; 6] = [
+        MenuEntry::Versus,
+        MenuEntry::Tournament,
+        MenuEntry::Practice,
+        MenuEntry::Editor,
+        MenuEntry::Leaderboard,
+        MenuEntry::Settings,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            MenuEntry::Versus => "Versus",
+            MenuEntry::Tournament => "Tournament",
+            MenuEntry::Practice => "Practice",
+            MenuEntry::Editor => "Arena Editor",
+            MenuEntry::Leaderboard => "Leaderboard",
+            MenuEntry::Settings => "Settings",
+        }
+    }
+}
+
+/// The main menu for choosing what to play.
+pub struct MenuState {
+    selected: usize,
+    chosen: Option<MenuEntry>,
+    /// Why the chosen entry couldn't be opened.
+    status: String,
+}
+
+impl MenuState {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            chosen: None,
+            status: String::new(),
+        }
+    }
+
+    /// Moves the selection of a list with the menu buttons.
+    /// Returns true if the select button was pressed.
+    pub fn navigate(button: &Button, selected: &mut usize, len: usize) -> bool {
+        match button {
+            Keyboard(BUTTON_MENU_UP | BUTTON_PLAYER_1_UP) => {
+                *selected = (*selected + len - 1) % len;
+                false
+            }
+            Keyboard(BUTTON_MENU_DOWN | BUTTON_PLAYER_1_DOWN) => {
+                *selected = (*selected + 1) % len;
+                false
+            }
+            Keyboard(BUTTON_MENU_SELECT) => true,
+            _ => false,
+        }
+    }
+
+    /// Draws a line of text with its baseline at the position.
+    pub fn render_text<GImpl: GraphicsImpl>(
+        ctx: &mut GraphicsOptions<GImpl>,
+        color: Color,
+        size: FontSize,
+        text: &str,
+        x: f64,
+        y: f64,
+    ) {
+        graphics::text(
+            color,
+            size,
+            text,
+            ctx.character_cache,
+            ctx.ctx.transform.trans(x, y),
+            ctx.graphics,
+        )
+        .expect("Unable to draw text!");
+    }
+
+    /// Renders a title and a list of items below it, highlighting the selected one.
+    pub fn render_list<GImpl: GraphicsImpl>(
+        ctx: &mut GraphicsOptions<GImpl>,
+        title: &str,
+        items: &[String],
+        selected: usize,
+    ) {
+        let x = (COURT_SIZE.0 as f64) / 4.0;
+        let mut y = (COURT_SIZE.1 as f64) / 4.0;
+
+        graphics::text(
+            MENU_COLOR,
+            MENU_TITLE_SIZE,
+            title,
+            ctx.character_cache,
+            ctx.ctx.transform.trans(x, y),
+            ctx.graphics,
+        )
+        .expect("Unable to draw text!");
+
+        for (index, item) in items.iter().enumerate() {
+            y += MENU_ITEM_GAP;
+
+            let (color, item) = if index == selected {
+                (MENU_SELECTED_COLOR, format!("> {}", item))
+            } else {
+                (MENU_COLOR, format!("  {}", item))
+            };
+            graphics::text(
+                color,
+                MENU_TEXT_SIZE,
+                &item,
+                ctx.character_cache,
+                ctx.ctx.transform.trans(x, y),
+                ctx.graphics,
+            )
+            .expect("Unable to draw text!");
+        }
+    }
+}
+
+impl Default for MenuState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for MenuState {
+    fn update(
+        mut self,
+        _: &UpdateArgs,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        let state = match self.chosen.take() {
+            None => return Ok(self),
+            Some(MenuEntry::Versus) => GameState::ProfileSelect(Box::default()),
+            Some(MenuEntry::Tournament) => GameState::TournamentSetup(Box::default()),
+            Some(MenuEntry::Practice) => GameState::Countdown(Box::new(CountdownState::new(
+                Duration::from_secs(3),
+                GameState::Breakout(Box::new(BreakoutState::new(options))),
+            ))),
+            Some(MenuEntry::Editor) => {
+                let path = PathBuf::from(EDITOR_DEFAULT_PATH);
+                match Arena::load_or_default(&path) {
+                    Ok(arena) => GameState::Editor(Box::new(EditorState::new(arena, path))),
+                    Err(err) => {
+                        // Saving in the editor would overwrite the file, so it stays closed
+                        self.status =
+                            format!("Unable to load the arena {}: {}", path.display(), err);
+                        return Ok(self);
+                    }
+                }
+            }
+            Some(MenuEntry::Leaderboard) => GameState::Leaderboard(Box::default()),
+            Some(MenuEntry::Settings) => {
+                GameState::Settings(Box::new(SettingsState::new(&options.config)))
+            }
+        };
+        Err(state)
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        _: &mut GameOptions<Impl>,
+    ) {
+        let mut items: Vec<String> = MenuEntry::ALL
+            .iter()
+            .map(|entry| entry.name().to_string())
+            .collect();
+        if !self.status.is_empty() {
+            items.push(self.status.clone());
+        }
+        Self::render_list(ctx, "Pong", &items, self.selected);
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if Self::navigate(button, &mut self.selected, MenuEntry::ALL.len()) {
+            self.chosen = Some(MenuEntry::ALL[self.selected]);
+        }
+    }
+
+    fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
@@ -1,21 +1,92 @@
+use crate::achievement::Achievements;
+use crate::audio::AudioBackend;
+use crate::config::Config;
+use crate::event::{EventQueue, GameEvent};
+use crate::game_state::bracket::BracketState;
+use crate::game_state::breakout::BreakoutState;
 use crate::game_state::countdown::CountdownState;
-use crate::game_state::play::PlayState;
+use crate::game_state::editor::EditorState;
+use crate::game_state::instant_replay::InstantReplayState;
+use crate::game_state::leaderboard::LeaderboardState;
+use crate::game_state::match_summary::MatchSummaryState;
+use crate::game_state::menu::MenuState;
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::game_state::profile_select::ProfileSelectState;
+use crate::game_state::settings::SettingsState;
+use crate::game_state::tournament_setup::TournamentSetupState;
+use crate::paddle::PaddleInput;
+use crate::profile::ProfileStore;
 use graphics::{CharacterCache, Context, Graphics};
 use piston::{Button, RenderArgs, UpdateArgs};
 use rand::Rng;
 
+pub mod bracket;
+pub mod breakout;
 pub mod countdown;
+pub mod editor;
+pub mod instant_replay;
+pub mod leaderboard;
+pub mod match_summary;
+pub mod menu;
 pub mod play;
+pub mod profile_select;
+pub mod settings;
+pub mod tournament_setup;
 
 pub enum GameState {
     Invalid(Box<Invalid>),
     Countdown(Box<CountdownState>),
     Play(Box<PlayState>),
+    Editor(Box<EditorState>),
+    Breakout(Box<BreakoutState>),
+    Menu(Box<MenuState>),
+    Settings(Box<SettingsState>),
+    InstantReplay(Box<InstantReplayState>),
+    MatchSummary(Box<MatchSummaryState>),
+    ProfileSelect(Box<ProfileSelectState>),
+    Leaderboard(Box<LeaderboardState>),
+    TournamentSetup(Box<TournamentSetupState>),
+    Bracket(Box<BracketState>),
+}
+
+impl GameState {
+    /// The name of the variant, shown in the debug overlay.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameState::Invalid(_) => "Invalid",
+            GameState::Countdown(_) => "Countdown",
+            GameState::Play(_) => "Play",
+            GameState::Editor(_) => "Editor",
+            GameState::Breakout(_) => "Breakout",
+            GameState::Menu(_) => "Menu",
+            GameState::Settings(_) => "Settings",
+            GameState::InstantReplay(_) => "InstantReplay",
+            GameState::MatchSummary(_) => "MatchSummary",
+            GameState::ProfileSelect(_) => "ProfileSelect",
+            GameState::Leaderboard(_) => "Leaderboard",
+            GameState::TournamentSetup(_) => "TournamentSetup",
+            GameState::Bracket(_) => "Bracket",
+        }
+    }
+
+    /// Sets the input of the paddle of a player from outside of the keyboard events, like a gamepad.
+    /// The countdown and the instant replay pass it on to the state after them,
+    /// other states without paddles ignore it.
+    pub fn set_paddle_input(&mut self, player: &PlayerId, input: PaddleInput) {
+        match self {
+            GameState::Countdown(state) => state.set_paddle_input(player, input),
+            GameState::Play(state) => state.set_paddle_input(player, input),
+            GameState::Breakout(state) => state.set_paddle_input(input),
+            GameState::InstantReplay(state) => state.set_paddle_input(player, input),
+            _ => {}
+        }
+    }
 }
 
 impl<Impl: GameImpl> GameStateTrait<Impl> for GameState {
     fn update(self, args: &UpdateArgs, options: &mut GameOptions<Impl>) -> Result<Self, GameState> {
-        match self {
+        let from = self.name();
+        let result = match self {
             GameState::Invalid(state) => state
                 .update(args, options)
                 .map(|state| GameState::Invalid(Box::new(state))),
@@ -27,7 +98,55 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for GameState {
             GameState::Play(state) => state
                 .update(args, options)
                 .map(|state| GameState::Play(Box::new(state))),
+
+            GameState::Editor(state) => state
+                .update(args, options)
+                .map(|state| GameState::Editor(Box::new(state))),
+
+            GameState::Breakout(state) => state
+                .update(args, options)
+                .map(|state| GameState::Breakout(Box::new(state))),
+
+            GameState::Menu(state) => state
+                .update(args, options)
+                .map(|state| GameState::Menu(Box::new(state))),
+
+            GameState::Settings(state) => state
+                .update(args, options)
+                .map(|state| GameState::Settings(Box::new(state))),
+
+            GameState::InstantReplay(state) => state
+                .update(args, options)
+                .map(|state| GameState::InstantReplay(Box::new(state))),
+
+            GameState::MatchSummary(state) => state
+                .update(args, options)
+                .map(|state| GameState::MatchSummary(Box::new(state))),
+
+            GameState::ProfileSelect(state) => state
+                .update(args, options)
+                .map(|state| GameState::ProfileSelect(Box::new(state))),
+
+            GameState::Leaderboard(state) => state
+                .update(args, options)
+                .map(|state| GameState::Leaderboard(Box::new(state))),
+
+            GameState::TournamentSetup(state) => state
+                .update(args, options)
+                .map(|state| GameState::TournamentSetup(Box::new(state))),
+
+            GameState::Bracket(state) => state
+                .update(args, options)
+                .map(|state| GameState::Bracket(Box::new(state))),
+        };
+
+        if let Err(state) = &result {
+            options.events.push(GameEvent::StateChanged {
+                from,
+                to: state.name(),
+            });
         }
+        result
     }
 
     fn render(
@@ -40,6 +159,16 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for GameState {
             GameState::Invalid(state) => state.render(ctx, args, options),
             GameState::Countdown(state) => state.render(ctx, args, options),
             GameState::Play(state) => state.render(ctx, args, options),
+            GameState::Editor(state) => state.render(ctx, args, options),
+            GameState::Breakout(state) => state.render(ctx, args, options),
+            GameState::Menu(state) => state.render(ctx, args, options),
+            GameState::Settings(state) => state.render(ctx, args, options),
+            GameState::InstantReplay(state) => state.render(ctx, args, options),
+            GameState::MatchSummary(state) => state.render(ctx, args, options),
+            GameState::ProfileSelect(state) => state.render(ctx, args, options),
+            GameState::Leaderboard(state) => state.render(ctx, args, options),
+            GameState::TournamentSetup(state) => state.render(ctx, args, options),
+            GameState::Bracket(state) => state.render(ctx, args, options),
         }
     }
 
@@ -48,6 +177,16 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for GameState {
             GameState::Invalid(state) => state.button_press(button, options),
             GameState::Countdown(state) => state.button_press(button, options),
             GameState::Play(state) => state.button_press(button, options),
+            GameState::Editor(state) => state.button_press(button, options),
+            GameState::Breakout(state) => state.button_press(button, options),
+            GameState::Menu(state) => state.button_press(button, options),
+            GameState::Settings(state) => state.button_press(button, options),
+            GameState::InstantReplay(state) => state.button_press(button, options),
+            GameState::MatchSummary(state) => state.button_press(button, options),
+            GameState::ProfileSelect(state) => state.button_press(button, options),
+            GameState::Leaderboard(state) => state.button_press(button, options),
+            GameState::TournamentSetup(state) => state.button_press(button, options),
+            GameState::Bracket(state) => state.button_press(button, options),
         }
     }
 
@@ -56,6 +195,34 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for GameState {
             GameState::Invalid(state) => state.button_release(button, options),
             GameState::Countdown(state) => state.button_release(button, options),
             GameState::Play(state) => state.button_release(button, options),
+            GameState::Editor(state) => state.button_release(button, options),
+            GameState::Breakout(state) => state.button_release(button, options),
+            GameState::Menu(state) => state.button_release(button, options),
+            GameState::Settings(state) => state.button_release(button, options),
+            GameState::InstantReplay(state) => state.button_release(button, options),
+            GameState::MatchSummary(state) => state.button_release(button, options),
+            GameState::ProfileSelect(state) => state.button_release(button, options),
+            GameState::Leaderboard(state) => state.button_release(button, options),
+            GameState::TournamentSetup(state) => state.button_release(button, options),
+            GameState::Bracket(state) => state.button_release(button, options),
+        }
+    }
+
+    fn mouse_move(&mut self, position: &[f64; 2], options: &GameOptions<Impl>) {
+        match self {
+            GameState::Invalid(state) => state.mouse_move(position, options),
+            GameState::Countdown(state) => state.mouse_move(position, options),
+            GameState::Play(state) => state.mouse_move(position, options),
+            GameState::Editor(state) => state.mouse_move(position, options),
+            GameState::Breakout(state) => state.mouse_move(position, options),
+            GameState::Menu(state) => state.mouse_move(position, options),
+            GameState::Settings(state) => state.mouse_move(position, options),
+            GameState::InstantReplay(state) => state.mouse_move(position, options),
+            GameState::MatchSummary(state) => state.mouse_move(position, options),
+            GameState::ProfileSelect(state) => state.mouse_move(position, options),
+            GameState::Leaderboard(state) => state.mouse_move(position, options),
+            GameState::TournamentSetup(state) => state.mouse_move(position, options),
+            GameState::Bracket(state) => state.mouse_move(position, options),
         }
     }
 }
@@ -63,9 +230,16 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for GameState {
 pub trait GameImpl {
     type Rng: Rng;
     type GraphicsImpl: GraphicsImpl;
+    type Audio: AudioBackend;
 }
 pub struct GameOptions<Impl: GameImpl> {
     pub rng: Impl::Rng,
+    pub config: Config,
+    pub audio: Impl::Audio,
+    /// The events of the updates since they were last dispatched.
+    pub events: EventQueue,
+    pub profiles: ProfileStore,
+    pub achievements: Achievements,
 }
 
 pub trait GraphicsImpl {
@@ -93,6 +267,9 @@ pub trait GameStateTrait<Impl: GameImpl>: Sized {
 
     fn button_press(&mut self, button: &Button, options: &GameOptions<Impl>);
     fn button_release(&mut self, button: &Button, options: &GameOptions<Impl>);
+    /// Called when the mouse cursor moved.
+    /// The position is in court coordinates with the origin in the top left corner.
+    fn mouse_move(&mut self, position: &[f64; 2], options: &GameOptions<Impl>);
 }
 
 // invalid state
@@ -118,4 +295,8 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for Invalid {
     fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {
         unimplemented!("Test State")
     }
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {
+        unimplemented!("Test State")
+    }
 }
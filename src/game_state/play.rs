@@ -1,64 +1,265 @@
+use crate::achievement::Toast;
+use crate::ai::Bot;
+use crate::arena::Arena;
 use crate::ball::Ball;
+use crate::event::{EventSubscriber, GameEvent};
+use crate::game_state::bracket::BracketState;
 use crate::game_state::countdown::CountdownState;
+use crate::game_state::editor::EditorState;
+use crate::game_state::instant_replay::InstantReplayState;
+use crate::game_state::match_summary::MatchSummaryState;
+use crate::game_state::menu::MenuState;
 use crate::game_state::{
     GameImpl, GameOptions, GameState, GameStateTrait, GraphicsImpl, GraphicsOptions,
 };
-use crate::paddle::Paddle;
+use crate::paddle::{Paddle, PaddleInput};
+use crate::profile::{MatchResult, Participant};
+use crate::replay::{RallyFrames, ReplayFrame};
+use crate::stats::MatchStats;
+use crate::tournament::Tournament;
 use crate::{
-    BALL_MULTIPLIER, BALL_SIZE, BUTTON_PLAYER_1_DOWN, BUTTON_PLAYER_1_UP, BUTTON_PLAYER_2_DOWN,
-    BUTTON_PLAYER_2_UP, SCORE_COLOR, SCORE_COUNTDOWN, SCORE_SIZE, SCORE_Y_GAP,
+    ACHIEVEMENT_TEXT_SIZE, ACHIEVEMENT_TOAST_COLOR, ACHIEVEMENT_TOAST_SIZE, BALL_SIZE,
+    BUTTON_PLAYER_1_DOWN, BUTTON_PLAYER_1_UP, BUTTON_PLAYER_2_DOWN, BUTTON_PLAYER_2_UP,
+    BUTTON_RETURN_TO_EDITOR, INSTANT_REPLAY_TIME, MENU_COLOR, MENU_SELECTED_COLOR, SCORE_COLOR,
+    SCORE_COUNTDOWN, SCORE_SIZE, SCORE_Y_GAP,
 };
-use crate::{PADDLE_BORDER_GAP, PADDLE_SIZE, START_BALL_VELOCITY, WINDOW_SIZE};
-use graphics::Transformed;
+use crate::{COURT_SIZE, START_BALL_VELOCITY};
+use graphics::{rectangle, Transformed};
 use piston::Button::Keyboard;
 use piston::{Button, RenderArgs, UpdateArgs};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use std::rc::Rc;
 use std::time::Duration;
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PlayerId {
     One,
     Two,
 }
 
+impl PlayerId {
+    /// The index of the player in arrays of player one and two.
+    pub fn index(&self) -> usize {
+        match self {
+            PlayerId::One => 0,
+            PlayerId::Two => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Player {
     pub paddle: Paddle,
     pub score: u32,
     pub id: PlayerId,
 }
 
+/// A running match.
+/// Cloning is cheap because the arena, the editor, the tournament and the frames of the rally are shared,
+/// which is used for rewinding.
+#[derive(Clone)]
 pub struct PlayState {
     player_one: Player,
     player_two: Player,
     ball: Ball,
+    arena: Rc<Arena>,
+    /// The editor this arena is test played from.
+    editor: Option<Rc<EditorState>>,
+    /// The tournament this match is part of.
+    tournament: Option<Rc<Tournament>>,
+    /// The points to win this match instead of the points of the config.
+    points_to_win: Option<u32>,
+    /// The last moments of the current rally, shown again after a point.
+    rally: RallyFrames,
+    /// The frames of the rally that are shown again.
+    rally_len: usize,
+    /// The time between two frames of the rally.
+    frame_time: f64,
+    stats: MatchStats,
+    /// Who plays player one and two.
+    participants: [Participant; 2],
+    /// The computer players controlling the paddles of player one and two.
+    bots: [Option<Bot>; 2],
+    /// Whether the start of the match has been reported.
+    started: bool,
+    return_to_editor: bool,
 }
 
 impl PlayState {
     pub fn new<Impl: GameImpl>(options: &mut GameOptions<Impl>) -> Self {
+        Self::with_arena(options, Arena::default())
+    }
+
+    pub fn with_arena<Impl: GameImpl>(options: &mut GameOptions<Impl>, arena: Arena) -> Self {
+        let (paddle_one_x, paddle_one_y) = arena.paddle_spawn(&PlayerId::One);
+        let (paddle_two_x, paddle_two_y) = arena.paddle_spawn(&PlayerId::Two);
+        let (ball_x, ball_y) = arena.ball_spawn(&mut options.rng);
+
         Self {
             player_one: Player {
-                paddle: Paddle::new(PADDLE_BORDER_GAP, (WINDOW_SIZE.1 as f64) / 2.0),
+                paddle: Paddle::new(paddle_one_x, paddle_one_y),
                 score: 0,
                 id: PlayerId::One,
             },
             player_two: Player {
-                paddle: Paddle::new(
-                    (WINDOW_SIZE.0 as f64) - PADDLE_BORDER_GAP - (PADDLE_SIZE.0 as f64),
-                    (WINDOW_SIZE.1 as f64) / 2.0,
-                ),
+                paddle: Paddle::new(paddle_two_x, paddle_two_y),
                 score: 0,
                 id: PlayerId::Two,
             },
-            ball: Ball {
-                x: (WINDOW_SIZE.0 as f64) / 2.0,
-                x_velocity: START_BALL_VELOCITY,
-                y: (WINDOW_SIZE.1 as f64) / 2.0,
-                y_velocity: options
+            ball: Ball::new(
+                ball_x,
+                ball_y,
+                START_BALL_VELOCITY,
+                options
                     .rng
                     .gen_range(-START_BALL_VELOCITY..START_BALL_VELOCITY),
-            },
+            ),
+            arena: Rc::new(arena),
+            editor: None,
+            tournament: None,
+            points_to_win: None,
+            return_to_editor: false,
+            rally: RallyFrames::new(),
+            rally_len: 0,
+            frame_time: 0.0,
+            stats: MatchStats::new(),
+            participants: Default::default(),
+            bots: [None, None],
+            started: false,
         }
     }
 
+    /// Continues a match from the given players and ball, for example from a replay.
+    pub fn with_players(player_one: Player, player_two: Player, ball: Ball, arena: Arena) -> Self {
+        Self {
+            player_one,
+            player_two,
+            ball,
+            arena: Rc::new(arena),
+            editor: None,
+            tournament: None,
+            points_to_win: None,
+            return_to_editor: false,
+            rally: RallyFrames::new(),
+            rally_len: 0,
+            frame_time: 0.0,
+            stats: MatchStats::new(),
+            participants: Default::default(),
+            bots: [None, None],
+            started: false,
+        }
+    }
+
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    pub fn player(&self, player: &PlayerId) -> &Player {
+        match player {
+            PlayerId::One => &self.player_one,
+            PlayerId::Two => &self.player_two,
+        }
+    }
+
+    pub fn ball(&self) -> &Ball {
+        &self.ball
+    }
+
+    /// Replaces the ball, for example to start a rally from a certain position.
+    pub fn set_ball(&mut self, ball: Ball) {
+        self.ball = ball;
+    }
+
+    /// Sets the score of the player, for example to play the end of a match.
+    pub fn set_score(&mut self, player: &PlayerId, score: u32) {
+        match player {
+            PlayerId::One => self.player_one.score = score,
+            PlayerId::Two => self.player_two.score = score,
+        }
+    }
+
+    /// Sets who plays player one and two, computer players take over their paddle.
+    pub fn set_participants(&mut self, participants: [Participant; 2]) {
+        self.bots = participants.clone().map(|participant| match participant {
+            Participant::Bot(difficulty) => Some(Bot::new(difficulty)),
+            _ => None,
+        });
+        self.participants = participants;
+    }
+
+    /// Lets computer players with any controller take over the paddles of player one and two,
+    /// for example to compare them without a window.
+    pub fn set_bots(&mut self, bots: [Option<Bot>; 2]) {
+        self.bots = bots;
+    }
+
+    pub fn participants(&self) -> &[Participant; 2] {
+        &self.participants
+    }
+
+    /// The statistics of the match so far.
+    pub fn stats(&self) -> &MatchStats {
+        &self.stats
+    }
+
+    /// Sets the editor that can be returned to while test playing.
+    pub fn set_editor(&mut self, editor: EditorState) {
+        self.editor = Some(Rc::new(editor));
+    }
+
+    /// Sets the tournament the result of the match is recorded in.
+    pub fn set_tournament(&mut self, tournament: Tournament) {
+        self.tournament = Some(Rc::new(tournament));
+    }
+
+    /// Ends this match once a player has the points, whatever the config says.
+    pub fn set_points_to_win(&mut self, points: u32) {
+        self.points_to_win = Some(points);
+    }
+
+    /// Sets the input of the paddle of a player, like the stick of their gamepad.
+    pub fn set_paddle_input(&mut self, player: &PlayerId, input: PaddleInput) {
+        let player = match player {
+            PlayerId::One => &mut self.player_one,
+            PlayerId::Two => &mut self.player_two,
+        };
+        *player.paddle.input_mut() = input;
+    }
+
+    /// Draws the notification about an unlocked achievement at the bottom of the court.
+    pub fn render_toast<GImpl: GraphicsImpl>(toast: &Toast, ctx: &mut GraphicsOptions<GImpl>) {
+        let (width, height) = ACHIEVEMENT_TOAST_SIZE;
+        let x = (COURT_SIZE.0 as f64 - width) / 2.0;
+        let y = COURT_SIZE.1 as f64 - height - SCORE_Y_GAP / 2.0;
+        rectangle(
+            ACHIEVEMENT_TOAST_COLOR,
+            [x, y, width, height],
+            ctx.ctx.transform,
+            ctx.graphics,
+        );
+
+        let gap = ACHIEVEMENT_TEXT_SIZE as f64 * 1.5;
+        let title = format!("{} unlocked {}", toast.profile, toast.name);
+        MenuState::render_text(
+            ctx,
+            MENU_SELECTED_COLOR,
+            ACHIEVEMENT_TEXT_SIZE,
+            &title,
+            x + gap / 2.0,
+            y + gap,
+        );
+        MenuState::render_text(
+            ctx,
+            MENU_COLOR,
+            ACHIEVEMENT_TEXT_SIZE,
+            &toast.description,
+            x + gap / 2.0,
+            y + gap * 2.0,
+        );
+    }
+
     fn render_score<GImpl: GraphicsImpl>(
         player: &PlayerId,
         score: u32,
@@ -68,11 +269,11 @@ impl PlayState {
             PlayerId::One => ctx
                 .ctx
                 .transform
-                .trans((WINDOW_SIZE.0 as f64) / 4.0, SCORE_Y_GAP),
+                .trans((COURT_SIZE.0 as f64) / 4.0, SCORE_Y_GAP),
             PlayerId::Two => ctx
                 .ctx
                 .transform
-                .trans((WINDOW_SIZE.0 as f64) / 4.0 * 3.0, SCORE_Y_GAP),
+                .trans((COURT_SIZE.0 as f64) / 4.0 * 3.0, SCORE_Y_GAP),
         };
 
         let score = format!("{}", score);
@@ -87,7 +288,9 @@ impl PlayState {
         .expect("Unable to draw text!");
     }
 
-    fn check_ball_paddle_collide<Impl: GameImpl>(
+    /// Bounces the ball back if it hit the paddle and speeds it up.
+    pub fn check_ball_paddle_collide<Impl: GameImpl>(
+        player: &PlayerId,
         paddle: &Paddle,
         ball: &mut Ball,
         options: &mut GameOptions<Impl>,
@@ -96,45 +299,139 @@ impl PlayState {
             return;
         }
 
+        let config = &options.config;
         // Invert the x velocity of the ball
         ball.x_velocity *= -1.0;
         // Increase x velocity
-        ball.x_velocity *= BALL_MULTIPLIER;
+        ball.x_velocity =
+            ball.x_velocity.signum() * config.ball.speed_after_hit(ball.x_velocity.abs());
         // Calculate a new y velocity
         ball.y_velocity = options
             .rng
             .gen_range(-START_BALL_VELOCITY..START_BALL_VELOCITY);
+        // A moving paddle slices the ball into the direction it moves
+        ball.y_velocity += paddle.velocity() * config.paddle.momentum_transfer;
+        // The paddle drags the side of the ball it touches along which makes it spin
+        ball.spin = -ball.x_velocity.signum() * paddle.velocity() * config.ball.spin_transfer;
+
+        ball.limit_speed(config.ball.max_speed);
+        options.events.push(GameEvent::PaddleHit {
+            player: *player,
+            speed: ball.speed(),
+            max_speed: config.ball.max_speed,
+        });
     }
 
-    fn check_ball_scored(self) -> Result<Self, GameState> {
+    fn check_ball_scored<Impl: GameImpl>(
+        self,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        // See if the ball is inside of a goal of the arena
+        if let Some(player) = self.arena.goal_scorer(
+            self.ball.x,
+            self.ball.y,
+            BALL_SIZE.0 as f64,
+            BALL_SIZE.1 as f64,
+        ) {
+            return Err(self.scored(&player, options));
+        }
+
         // See if an someone has scored
         if self.ball.x <= 0.0 - (BALL_SIZE.0 as f64) {
-            return Err(self.scored(&PlayerId::Two));
-        } else if self.ball.x >= (WINDOW_SIZE.0 as f64) {
-            return Err(self.scored(&PlayerId::One));
+            return Err(self.scored(&PlayerId::Two, options));
+        } else if self.ball.x >= (COURT_SIZE.0 as f64) {
+            return Err(self.scored(&PlayerId::One, options));
         }
-        return Ok(self);
+        Ok(self)
     }
 
-    fn scored(mut self, player: &PlayerId) -> GameState {
+    fn scored<Impl: GameImpl>(
+        mut self,
+        player: &PlayerId,
+        options: &mut GameOptions<Impl>,
+    ) -> GameState {
         // Reset the ball position
-        self.ball.x = (WINDOW_SIZE.0 as f64) / 2.0;
-        self.ball.y = (WINDOW_SIZE.1 as f64) / 2.0;
+        let (ball_x, ball_y) = self.arena.ball_spawn(&mut options.rng);
+        self.ball.x = ball_x;
+        self.ball.y = ball_y;
         // Reset ball velocity
         self.ball.x_velocity = START_BALL_VELOCITY;
+        self.ball.spin = 0.0;
 
         // Increase the score of the player
         match player {
             PlayerId::One => self.player_one.score += 1,
             PlayerId::Two => self.player_two.score += 1,
         }
+        let event = GameEvent::Scored {
+            player: *player,
+            score: (self.player_one.score, self.player_two.score),
+        };
+        self.stats.handle(&event);
+        options.events.push(event);
 
-        GameState::Countdown(Box::new(CountdownState::new(
-            Duration::from_secs_f64(SCORE_COUNTDOWN),
-            GameState::Play(Box::new(self)),
+        // Show the end of the rally again before the next one starts or the match ends
+        let rally = mem::take(&mut self.rally).frames(self.rally_len);
+        let arena = self.arena().clone();
+        let frame_time = self.frame_time;
+        let points_to_win = self.points_to_win.or(options.config.rules.points_to_win);
+        let next = match self.winner(points_to_win) {
+            Some(winner) => {
+                let result = MatchResult {
+                    players: mem::take(&mut self.participants),
+                    winner,
+                    score: (self.player_one.score, self.player_two.score),
+                    longest_rally: self.stats.longest_rally(),
+                };
+                options.events.push(GameEvent::MatchOver(result.clone()));
+                let stats = mem::take(&mut self.stats);
+                let next = self.match_over(&result);
+                GameState::MatchSummary(Box::new(MatchSummaryState::new(stats, result, next)))
+            }
+            None => GameState::Countdown(Box::new(CountdownState::new(
+                Duration::from_secs_f64(SCORE_COUNTDOWN),
+                GameState::Play(Box::new(self)),
+            ))),
+        };
+        if rally.is_empty() {
+            return next;
+        }
+        GameState::InstantReplay(Box::new(InstantReplayState::new(
+            rally, arena, frame_time, next,
         )))
     }
 
+    /// The player that has reached the points to win, if any.
+    pub fn winner(&self, points_to_win: Option<u32>) -> Option<PlayerId> {
+        let points = points_to_win?;
+        [&self.player_one, &self.player_two]
+            .into_iter()
+            .find(|player| player.score >= points)
+            .map(|player| player.id)
+    }
+
+    /// The state after the match has ended, the editor when test playing,
+    /// the bracket with the result when playing a tournament and the menu otherwise.
+    fn match_over(mut self, result: &MatchResult) -> GameState {
+        if let Some(tournament) = self.tournament.take() {
+            let mut tournament = Rc::unwrap_or_clone(tournament);
+            tournament.record(result.winner, result.score);
+            return GameState::Bracket(Box::new(BracketState::new(tournament)));
+        }
+        match self.editor.take() {
+            Some(editor) => GameState::Editor(Box::new(Rc::unwrap_or_clone(editor))),
+            None => GameState::Menu(Box::default()),
+        }
+    }
+
+    /// Keeps the frame after an update for the instant replay, dropping the ones that are too old.
+    fn record_rally(&mut self, dt: f64) {
+        self.rally_len = (INSTANT_REPLAY_TIME / dt).ceil() as usize;
+        self.rally
+            .push(ReplayFrame::from_play(self), self.rally_len);
+        self.frame_time = dt;
+    }
+
     pub fn is_box_colliding_with_box(
         b1x: f64,
         b1y: f64,
@@ -155,29 +452,88 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for PlayState {
         args: &UpdateArgs,
         options: &mut GameOptions<Impl>,
     ) -> Result<Self, GameState> {
-        let y_range = 0f64..(WINDOW_SIZE.1 as f64);
+        if self.return_to_editor {
+            if let Some(editor) = self.editor.take() {
+                return Err(GameState::Editor(Box::new(Rc::unwrap_or_clone(editor))));
+            }
+            self.return_to_editor = false;
+        }
+
+        if !self.started {
+            self.started = true;
+            options.events.push(GameEvent::MatchStarted {
+                players: self.participants.clone(),
+            });
+        }
+
+        let y_range = 0f64..(COURT_SIZE.1 as f64);
+        let first_event = options.events.events().len();
+
+        // The computer players press the buttons before the paddles move
+        let players = [&mut self.player_one, &mut self.player_two];
+        for (bot, player) in self.bots.iter_mut().zip(players) {
+            if let Some(bot) = bot {
+                bot.update(
+                    &player.id,
+                    &mut player.paddle,
+                    &self.ball,
+                    &self.arena,
+                    &options.config,
+                    &mut options.rng,
+                    args.dt,
+                );
+            }
+        }
 
-        self.player_one.paddle.update(args, y_range.clone());
-        Self::check_ball_paddle_collide(&self.player_one.paddle, &mut self.ball, options);
+        self.player_one
+            .paddle
+            .update(args, y_range.clone(), &options.config.paddle);
+        Self::check_ball_paddle_collide(
+            &self.player_one.id,
+            &self.player_one.paddle,
+            &mut self.ball,
+            options,
+        );
 
-        self.player_two.paddle.update(args, y_range.clone());
-        Self::check_ball_paddle_collide(&self.player_two.paddle, &mut self.ball, options);
+        self.player_two
+            .paddle
+            .update(args, y_range.clone(), &options.config.paddle);
+        Self::check_ball_paddle_collide(
+            &self.player_two.id,
+            &self.player_two.paddle,
+            &mut self.ball,
+            options,
+        );
 
-        self.ball.update(args, y_range);
+        let mut bounced = self.ball.update(args, y_range, &options.config.ball);
+        for obstacle in &self.arena.obstacles {
+            bounced |= self.ball.bounce_off_box(obstacle);
+        }
+        if bounced {
+            options.events.push(GameEvent::WallBounce);
+        }
+
+        // Count the hits of this update in the statistics, points are counted when scoring
+        self.stats.advance(args.dt);
+        for event in &options.events.events()[first_event..] {
+            self.stats.handle(event);
+        }
 
-        self.check_ball_scored()
+        self.record_rally(args.dt);
+        self.check_ball_scored(options)
     }
 
     fn render(
         &mut self,
         ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
-        args: &RenderArgs,
-        _: &mut GameOptions<Impl>,
+        _: &RenderArgs,
+        options: &mut GameOptions<Impl>,
     ) {
         // flip the screen vertically because the origin is in the top left corner
         let transform = ctx.ctx.transform;
-        ctx.ctx.transform = transform.trans(0.0, args.window_size[1] as f64).flip_v();
+        ctx.ctx.transform = transform.trans(0.0, COURT_SIZE.1 as f64).flip_v();
 
+        self.arena.render(ctx.ctx, ctx.graphics);
         self.player_one.paddle.render(ctx.ctx, ctx.graphics);
         self.player_two.paddle.render(ctx.ctx, ctx.graphics);
         self.ball.render(ctx.ctx, ctx.graphics);
@@ -188,6 +544,10 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for PlayState {
         // Render score
         Self::render_score(&self.player_one.id, self.player_one.score, ctx);
         Self::render_score(&self.player_two.id, self.player_two.score, ctx);
+
+        if let Some(toast) = options.achievements.toast() {
+            Self::render_toast(toast, ctx);
+        }
     }
 
     fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
@@ -203,6 +563,9 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for PlayState {
         if let Keyboard(BUTTON_PLAYER_2_DOWN) = button {
             self.player_two.paddle.input_mut().press_down();
         }
+        if let Keyboard(BUTTON_RETURN_TO_EDITOR) = button {
+            self.return_to_editor = true;
+        }
     }
 
     fn button_release(&mut self, button: &Button, _: &GameOptions<Impl>) {
@@ -219,4 +582,6 @@ impl<Impl: GameImpl> GameStateTrait<Impl> for PlayState {
             self.player_two.paddle.input_mut().release_down();
         }
     }
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
 }
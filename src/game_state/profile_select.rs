@@ -0,0 +1,257 @@
+use std::time::Duration;
+
+use piston::Button::Keyboard;
+use piston::{Button, Key, RenderArgs, UpdateArgs};
+
+use crate::game_state::countdown::CountdownState;
+use crate::game_state::menu::MenuState;
+use crate::game_state::play::PlayState;
+use crate::game_state::settings::cycle;
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::profile::{Participant, ProfileStore, GUEST};
+use crate::{
+    BUTTON_MENU_BACK, BUTTON_MENU_LEFT, BUTTON_MENU_RIGHT, BUTTON_MENU_SELECT,
+    BUTTON_PROFILE_SHIFT, PROFILE_NAME_LENGTH, VERSUS_POINTS_TO_WIN,
+};
+
+/// The entries of the profile selection, in the order they are shown.
+const ENTRIES: usize = 5;
+const ENTRY_PLAYER_ONE: usize = 0;
+const ENTRY_PLAYER_TWO: usize = 1;
+const ENTRY_NEW: usize = 2;
+const ENTRY_START: usize = 3;
+const ENTRY_BACK: usize = 4;
+
+/// Chooses the profiles or computer players of both players before a versus match
+/// and creates new profiles.
+pub struct ProfileSelectState {
+    /// Who plays player one and two.
+    chosen: [Participant; 2],
+    selected: usize,
+    /// The name of a new profile while it is typed.
+    new_name: Option<String>,
+    /// A typed name that is created in the next update.
+    create: Option<String>,
+    shift: bool,
+    start: bool,
+    back: bool,
+    status: String,
+}
+
+impl ProfileSelectState {
+    pub fn new() -> Self {
+        Self {
+            chosen: Default::default(),
+            selected: 0,
+            new_name: None,
+            create: None,
+            shift: false,
+            start: false,
+            back: false,
+            status: String::new(),
+        }
+    }
+
+    /// Who plays player one and two.
+    pub fn chosen(&self) -> &[Participant; 2] {
+        &self.chosen
+    }
+
+    /// Chooses the next or previous participant for the player,
+    /// in the order guest, profiles and computer players.
+    fn change(&mut self, slot: usize, direction: isize, profiles: &ProfileStore) {
+        let mut participants = vec![Participant::Guest];
+        participants.extend(profiles.participants());
+
+        let index = participants
+            .iter()
+            .position(|participant| *participant == self.chosen[slot])
+            .unwrap_or(0);
+        self.chosen[slot] = participants[cycle(index, direction, participants.len())].clone();
+    }
+
+    /// The label of a player, with the wins, losses and the rating of a profile.
+    fn label(participant: &Participant, profiles: &ProfileStore) -> String {
+        let rating = profiles.ratings().rating(participant).round();
+        match participant {
+            Participant::Guest => GUEST.to_string(),
+            Participant::Profile(name) => match profiles.get(name) {
+                Some(profile) => format!(
+                    "{} ({}-{}, rating {})",
+                    name, profile.wins, profile.losses, rating
+                ),
+                None => name.clone(),
+            },
+            Participant::Bot(difficulty) => format!("{} (rating {})", difficulty.name(), rating),
+        }
+    }
+
+    /// Types into the name of the new profile.
+    fn type_key(&mut self, key: Key) {
+        let Some(name) = &mut self.new_name else {
+            return;
+        };
+
+        match key {
+            BUTTON_MENU_SELECT => {
+                self.create = self.new_name.take();
+            }
+            BUTTON_MENU_BACK => {
+                // Deleting from an empty name stops typing
+                if name.pop().is_none() {
+                    self.new_name = None;
+                }
+            }
+            _ => {
+                let Some(char) = char::from_u32(key as u32) else {
+                    return;
+                };
+                if (char.is_ascii_alphanumeric() || char == ' ')
+                    && name.chars().count() < PROFILE_NAME_LENGTH
+                {
+                    name.push(if self.shift {
+                        char.to_ascii_uppercase()
+                    } else {
+                        char
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Default for ProfileSelectState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for ProfileSelectState {
+    fn update(
+        mut self,
+        _: &UpdateArgs,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        if let Some(name) = self.create.take() {
+            let name = name.trim().to_string();
+            self.status = match options.profiles.create(&name) {
+                Ok(()) => {
+                    // Give the new profile to the first guest
+                    if let Some(slot) = self
+                        .chosen
+                        .iter_mut()
+                        .find(|slot| **slot == Participant::Guest)
+                    {
+                        *slot = Participant::Profile(name.clone());
+                    }
+                    format!("Created the profile {}", name)
+                }
+                Err(err) => format!("Unable to create the profile: {}", err),
+            };
+        }
+
+        if self.back {
+            return Err(GameState::Menu(Box::default()));
+        }
+        if self.start {
+            self.start = false;
+            if matches!(self.chosen[0], Participant::Profile(_)) && self.chosen[0] == self.chosen[1]
+            {
+                self.status = "Both players need different profiles".to_string();
+                return Ok(self);
+            }
+
+            let mut play = PlayState::new(options);
+            play.set_participants(self.chosen.clone());
+            play.set_points_to_win(
+                options
+                    .config
+                    .rules
+                    .points_to_win
+                    .unwrap_or(VERSUS_POINTS_TO_WIN),
+            );
+            return Err(GameState::Countdown(Box::new(CountdownState::new(
+                Duration::from_secs(3),
+                GameState::Play(Box::new(play)),
+            ))));
+        }
+        Ok(self)
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        options: &mut GameOptions<Impl>,
+    ) {
+        let mut items = vec![String::new(); ENTRIES];
+        items[ENTRY_PLAYER_ONE] = format!(
+            "Player one: {}",
+            Self::label(&self.chosen[0], &options.profiles)
+        );
+        items[ENTRY_PLAYER_TWO] = format!(
+            "Player two: {}",
+            Self::label(&self.chosen[1], &options.profiles)
+        );
+        items[ENTRY_NEW] = match &self.new_name {
+            Some(name) => format!("New profile: {}_", name),
+            None => "New profile".to_string(),
+        };
+        items[ENTRY_START] = "Start".to_string();
+        items[ENTRY_BACK] = "Back".to_string();
+        if let Participant::Profile(name) = &self.chosen[0] {
+            let bot = options.profiles.ratings().recommend_bot(&self.chosen[0]);
+            items.push(format!("Fair opponent for {}: {}", name, bot.name()));
+        }
+        if !self.status.is_empty() {
+            items.push(self.status.clone());
+        }
+
+        MenuState::render_list(ctx, "Profiles", &items, self.selected);
+    }
+
+    fn button_press(&mut self, button: &Button, options: &GameOptions<Impl>) {
+        if let Keyboard(key) = button {
+            if BUTTON_PROFILE_SHIFT.contains(key) {
+                self.shift = true;
+            }
+            if self.new_name.is_some() {
+                self.type_key(*key);
+                return;
+            }
+        }
+
+        if MenuState::navigate(button, &mut self.selected, ENTRIES) {
+            match self.selected {
+                ENTRY_PLAYER_ONE | ENTRY_PLAYER_TWO => {
+                    self.change(self.selected, 1, &options.profiles)
+                }
+                ENTRY_NEW => self.new_name = Some(String::new()),
+                ENTRY_START => self.start = true,
+                ENTRY_BACK => self.back = true,
+                _ => {}
+            }
+        }
+
+        match button {
+            Keyboard(BUTTON_MENU_LEFT) if self.selected <= ENTRY_PLAYER_TWO => {
+                self.change(self.selected, -1, &options.profiles)
+            }
+            Keyboard(BUTTON_MENU_RIGHT) if self.selected <= ENTRY_PLAYER_TWO => {
+                self.change(self.selected, 1, &options.profiles)
+            }
+            Keyboard(BUTTON_MENU_BACK) => self.back = true,
+            _ => {}
+        }
+    }
+
+    fn button_release(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if let Keyboard(key) = button {
+            if BUTTON_PROFILE_SHIFT.contains(key) {
+                self.shift = false;
+            }
+        }
+    }
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
@@ -0,0 +1,137 @@
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+
+use crate::config::{Config, DisplayConfig, DisplayMode};
+use crate::game_state::menu::MenuState;
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::{BUTTON_MENU_BACK, BUTTON_MENU_LEFT, BUTTON_MENU_RIGHT, FPS_CAPS};
+
+/// The entries of the settings, in the order they are shown.
+const ENTRIES: usize = 4;
+const ENTRY_MODE: usize = 0;
+const ENTRY_VSYNC: usize = 1;
+const ENTRY_MAX_FPS: usize = 2;
+const ENTRY_BACK: usize = 3;
+
+/// Changes the display settings and saves them to the config file.
+pub struct SettingsState {
+    display: DisplayConfig,
+    selected: usize,
+    back: bool,
+    status: String,
+}
+
+impl SettingsState {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            display: config.display.clone(),
+            selected: 0,
+            back: false,
+            status: String::new(),
+        }
+    }
+
+    /// The display settings as they are currently chosen.
+    pub fn display(&self) -> &DisplayConfig {
+        &self.display
+    }
+
+    /// Changes the value of the selected entry into the direction.
+    fn change(&mut self, direction: isize) {
+        match self.selected {
+            ENTRY_MODE => {
+                let modes = [
+                    DisplayMode::Windowed,
+                    DisplayMode::Borderless,
+                    DisplayMode::Exclusive,
+                ];
+                let index = modes
+                    .iter()
+                    .position(|mode| *mode == self.display.current_mode())
+                    .unwrap_or(0);
+                // A mode chosen here replaces the one of the command line and is saved
+                self.display.mode = modes[cycle(index, direction, modes.len())];
+                self.display.mode_override = None;
+            }
+            ENTRY_VSYNC => self.display.vsync = !self.display.vsync,
+            ENTRY_MAX_FPS => {
+                // Use the closest cap if the config file contains a different one
+                let index = FPS_CAPS
+                    .iter()
+                    .position(|fps| *fps >= self.display.max_fps)
+                    .unwrap_or(FPS_CAPS.len() - 1);
+                self.display.max_fps = FPS_CAPS[cycle(index, direction, FPS_CAPS.len())];
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Moves the index into the direction, wrapping around at both ends.
+pub fn cycle(index: usize, direction: isize, len: usize) -> usize {
+    (index as isize + direction).rem_euclid(len as isize) as usize
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for SettingsState {
+    fn update(
+        mut self,
+        _: &UpdateArgs,
+        options: &mut GameOptions<Impl>,
+    ) -> Result<Self, GameState> {
+        if self.display != options.config.display {
+            options.config.display = self.display.clone();
+
+            // Persist the settings right away
+            self.status = match options.config.save_default() {
+                Ok(()) => String::new(),
+                Err(err) => format!("Unable to save the settings: {}", err),
+            };
+        }
+
+        if self.back {
+            return Err(GameState::Menu(Box::default()));
+        }
+        Ok(self)
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        _: &mut GameOptions<Impl>,
+    ) {
+        let mut items = vec![String::new(); ENTRIES];
+        items[ENTRY_MODE] = format!("Display mode: {}", self.display.current_mode());
+        items[ENTRY_VSYNC] = format!(
+            "Vsync: {} (needs a restart)",
+            if self.display.vsync { "on" } else { "off" }
+        );
+        items[ENTRY_MAX_FPS] = format!("Frame rate cap: {}", self.display.max_fps);
+        items[ENTRY_BACK] = "Back".to_string();
+        if !self.status.is_empty() {
+            items.push(self.status.clone());
+        }
+
+        MenuState::render_list(ctx, "Settings", &items, self.selected);
+    }
+
+    fn button_press(&mut self, button: &Button, _: &GameOptions<Impl>) {
+        if MenuState::navigate(button, &mut self.selected, ENTRIES) {
+            match self.selected {
+                ENTRY_BACK => self.back = true,
+                _ => self.change(1),
+            }
+        }
+
+        match button {
+            Keyboard(BUTTON_MENU_LEFT) => self.change(-1),
+            Keyboard(BUTTON_MENU_RIGHT) => self.change(1),
+            Keyboard(BUTTON_MENU_BACK) => self.back = true,
+            _ => {}
+        }
+    }
+
+    fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
@@ -0,0 +1,177 @@
+use piston::Button::Keyboard;
+use piston::{Button, RenderArgs, UpdateArgs};
+
+use crate::game_state::bracket::BracketState;
+use crate::game_state::menu::MenuState;
+use crate::game_state::settings::cycle;
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions};
+use crate::profile::{Participant, ProfileStore};
+use crate::tournament::{Format, Tournament};
+use crate::{
+    BUTTON_MENU_BACK, BUTTON_MENU_LEFT, BUTTON_MENU_RIGHT, MENU_COLOR, TOURNAMENT_COLUMNS,
+    TOURNAMENT_MAX_PLAYERS, TOURNAMENT_ROW_GAP, TOURNAMENT_TEXT_SIZE,
+};
+
+/// The entries of the tournament setup, in the order they are shown.
+const ENTRIES: usize = 5;
+const ENTRY_FORMAT: usize = 0;
+const ENTRY_ADD: usize = 1;
+const ENTRY_REMOVE: usize = 2;
+const ENTRY_START: usize = 3;
+const ENTRY_BACK: usize = 4;
+
+/// Chooses the format and the players of a tournament.
+pub struct TournamentSetupState {
+    format: usize,
+    entrants: Vec<Participant>,
+    /// The player that is entered next.
+    candidate: Option<Participant>,
+    selected: usize,
+    start: bool,
+    back: bool,
+    status: String,
+}
+
+impl TournamentSetupState {
+    pub fn new() -> Self {
+        Self {
+            format: 0,
+            entrants: Vec::new(),
+            candidate: None,
+            selected: 0,
+            start: false,
+            back: false,
+            status: String::new(),
+        }
+    }
+
+    pub fn format(&self) -> Format {
+        Format::ALL[self.format]
+    }
+
+    /// The players entered so far, in the order of their seeds.
+    pub fn entrants(&self) -> &[Participant] {
+        &self.entrants
+    }
+
+    /// The player that is entered next, the first profile or computer player until another is chosen.
+    fn candidate(&self, profiles: &ProfileStore) -> Participant {
+        match &self.candidate {
+            Some(candidate) => candidate.clone(),
+            None => profiles.participants().remove(0),
+        }
+    }
+
+    /// Chooses the next or previous profile or computer player to enter.
+    fn change(&mut self, direction: isize, profiles: &ProfileStore) {
+        let participants = profiles.participants();
+        let candidate = self.candidate(profiles);
+        let index = participants
+            .iter()
+            .position(|participant| *participant == candidate)
+            .unwrap_or(0);
+        self.candidate = Some(participants[cycle(index, direction, participants.len())].clone());
+    }
+
+    /// Enters the candidate. Computer players can be entered more than once, profiles only once.
+    fn add(&mut self, profiles: &ProfileStore) {
+        let candidate = self.candidate(profiles);
+        self.status = if self.entrants.len() >= TOURNAMENT_MAX_PLAYERS {
+            format!(
+                "A tournament has at most {} players",
+                TOURNAMENT_MAX_PLAYERS
+            )
+        } else if matches!(candidate, Participant::Profile(_)) && self.entrants.contains(&candidate)
+        {
+            format!("{} already plays in the tournament", candidate.name())
+        } else {
+            self.entrants.push(candidate);
+            String::new()
+        };
+    }
+}
+
+impl Default for TournamentSetupState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Impl: GameImpl> GameStateTrait<Impl> for TournamentSetupState {
+    fn update(mut self, _: &UpdateArgs, _: &mut GameOptions<Impl>) -> Result<Self, GameState> {
+        if self.back {
+            return Err(GameState::Menu(Box::default()));
+        }
+        if self.start {
+            self.start = false;
+            match Tournament::new(self.format(), self.entrants.clone()) {
+                Ok(tournament) => {
+                    return Err(GameState::Bracket(Box::new(BracketState::new(tournament))))
+                }
+                Err(err) => self.status = err.to_string(),
+            }
+        }
+        Ok(self)
+    }
+
+    fn render(
+        &mut self,
+        ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+        _: &RenderArgs,
+        options: &mut GameOptions<Impl>,
+    ) {
+        let mut items = vec![String::new(); ENTRIES];
+        items[ENTRY_FORMAT] = format!("Format: {}", self.format().name());
+        items[ENTRY_ADD] = format!("Add: {}", self.candidate(&options.profiles).name());
+        items[ENTRY_REMOVE] = "Remove the last player".to_string();
+        items[ENTRY_START] = format!("Start with {} players", self.entrants.len());
+        items[ENTRY_BACK] = "Back".to_string();
+        if !self.status.is_empty() {
+            items.push(self.status.clone());
+        }
+        MenuState::render_list(ctx, "Tournament", &items, self.selected);
+
+        let [_, x] = TOURNAMENT_COLUMNS;
+        let mut y = TOURNAMENT_ROW_GAP * 2.0;
+        for (index, entrant) in self.entrants.iter().enumerate() {
+            y += TOURNAMENT_ROW_GAP;
+            let text = format!("{}. {}", index + 1, entrant.name());
+            MenuState::render_text(ctx, MENU_COLOR, TOURNAMENT_TEXT_SIZE, &text, x, y);
+        }
+    }
+
+    fn button_press(&mut self, button: &Button, options: &GameOptions<Impl>) {
+        if MenuState::navigate(button, &mut self.selected, ENTRIES) {
+            match self.selected {
+                ENTRY_FORMAT => self.format = cycle(self.format, 1, Format::ALL.len()),
+                ENTRY_ADD => self.add(&options.profiles),
+                ENTRY_REMOVE => {
+                    self.entrants.pop();
+                    self.status.clear();
+                }
+                ENTRY_START => self.start = true,
+                ENTRY_BACK => self.back = true,
+                _ => {}
+            }
+        }
+
+        let direction = match button {
+            Keyboard(BUTTON_MENU_LEFT) => -1,
+            Keyboard(BUTTON_MENU_RIGHT) => 1,
+            Keyboard(BUTTON_MENU_BACK) => {
+                self.back = true;
+                return;
+            }
+            _ => return,
+        };
+        match self.selected {
+            ENTRY_FORMAT => self.format = cycle(self.format, direction, Format::ALL.len()),
+            ENTRY_ADD => self.change(direction, &options.profiles),
+            _ => {}
+        }
+    }
+
+    fn button_release(&mut self, _: &Button, _: &GameOptions<Impl>) {}
+
+    fn mouse_move(&mut self, _: &[f64; 2], _: &GameOptions<Impl>) {}
+}
@@ -0,0 +1,265 @@
+use crate::game_state::play::PlayerId;
+use crate::paddle::PaddleInput;
+use crate::GAMEPAD_DEAD_ZONE;
+
+/// Tells the gamepads apart, stays the same while a gamepad is connected.
+pub type GamepadId = usize;
+
+/// What happened on a gamepad, reported by a [`GamepadBackend`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    /// The vertical position of the stick from -1 (down) to 1 (up).
+    /// The buttons of the d-pad move it all the way.
+    Axis(GamepadId, f64),
+    /// The player of the gamepad wants to control the other paddle.
+    SwitchSides(GamepadId),
+}
+
+/// Reads the gamepads, chosen by the game like the [`crate::audio::AudioBackend`].
+pub trait GamepadBackend {
+    /// The events since the last call, in the order they happened.
+    fn poll(&mut self) -> Vec<GamepadEvent>;
+}
+
+/// Never finds a gamepad, for running the game without the gamepad feature.
+#[derive(Default)]
+pub struct NoGamepads;
+
+impl GamepadBackend for NoGamepads {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        Vec::new()
+    }
+}
+
+/// Reports the events it is given instead of reading real gamepads.
+/// Tests use it to connect gamepads and move their sticks without hardware.
+#[derive(Default)]
+pub struct MockGamepads {
+    events: Vec<GamepadEvent>,
+}
+
+impl MockGamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(&mut self, gamepad: GamepadId) {
+        self.events.push(GamepadEvent::Connected(gamepad));
+    }
+
+    pub fn disconnect(&mut self, gamepad: GamepadId) {
+        self.events.push(GamepadEvent::Disconnected(gamepad));
+    }
+
+    pub fn move_stick(&mut self, gamepad: GamepadId, position: f64) {
+        self.events.push(GamepadEvent::Axis(gamepad, position));
+    }
+
+    pub fn switch_sides(&mut self, gamepad: GamepadId) {
+        self.events.push(GamepadEvent::SwitchSides(gamepad));
+    }
+}
+
+impl GamepadBackend for MockGamepads {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Reads the gamepads with gilrs, connected gamepads are found while the game runs.
+/// Without a working gamepad library the game is played with the keyboard only.
+#[cfg(feature = "gamepad")]
+pub struct GilrsGamepads {
+    gilrs: Option<gilrs::Gilrs>,
+    /// The gamepads that were connected before the game started, reported with the first poll.
+    connected: Vec<GamepadId>,
+}
+
+#[cfg(feature = "gamepad")]
+impl GilrsGamepads {
+    pub fn new() -> Self {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => Self {
+                connected: gilrs.gamepads().map(|(id, _)| id.into()).collect(),
+                gilrs: Some(gilrs),
+            },
+            Err(err) => {
+                eprintln!(
+                    "Unable to read the gamepads, only the keyboard works: {}",
+                    err
+                );
+                Self {
+                    gilrs: None,
+                    connected: Vec::new(),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl Default for GilrsGamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadBackend for GilrsGamepads {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        use gilrs::{Axis, Button, EventType};
+
+        let mut events: Vec<GamepadEvent> = self
+            .connected
+            .drain(..)
+            .map(GamepadEvent::Connected)
+            .collect();
+        let Some(gilrs) = &mut self.gilrs else {
+            return events;
+        };
+        while let Some(event) = gilrs.next_event() {
+            let id = event.id.into();
+            let event = match event.event {
+                EventType::Connected => GamepadEvent::Connected(id),
+                EventType::Disconnected => GamepadEvent::Disconnected(id),
+                EventType::AxisChanged(Axis::LeftStickY, position, _) => {
+                    GamepadEvent::Axis(id, position as f64)
+                }
+                EventType::ButtonPressed(Button::DPadUp, _) => GamepadEvent::Axis(id, 1.0),
+                EventType::ButtonPressed(Button::DPadDown, _) => GamepadEvent::Axis(id, -1.0),
+                EventType::ButtonReleased(Button::DPadUp | Button::DPadDown, _) => {
+                    GamepadEvent::Axis(id, 0.0)
+                }
+                EventType::ButtonPressed(Button::Select, _) => GamepadEvent::SwitchSides(id),
+                _ => continue,
+            };
+            events.push(event);
+        }
+        events
+    }
+}
+
+/// The gamepads of the game, gamepads are only read with the gamepad feature.
+#[cfg(feature = "gamepad")]
+pub type DefaultGamepads = GilrsGamepads;
+#[cfg(not(feature = "gamepad"))]
+pub type DefaultGamepads = NoGamepads;
+
+/// Assigns the connected gamepads to the players and turns their sticks into paddle inputs.
+/// The first gamepad controls player one and the second one player two,
+/// a gamepad that is connected while both players have one waits until a player loses theirs.
+pub struct Gamepads<B: GamepadBackend> {
+    backend: B,
+    /// The connected gamepads in the order they were connected.
+    connected: Vec<GamepadId>,
+    /// The gamepads of player one and two.
+    players: [Option<GamepadId>; 2],
+    /// The last inputs of player one and two, so only changes are reported.
+    inputs: [PaddleInput; 2],
+}
+
+impl<B: GamepadBackend> Gamepads<B> {
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            connected: Vec::new(),
+            players: [None, None],
+            inputs: [PaddleInput::None, PaddleInput::None],
+        }
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// The gamepad that controls the player.
+    pub fn gamepad(&self, player: &PlayerId) -> Option<GamepadId> {
+        self.players[player.index()]
+    }
+
+    /// The player the gamepad controls.
+    pub fn player(&self, gamepad: GamepadId) -> Option<PlayerId> {
+        match self.players.iter().position(|&id| id == Some(gamepad)) {
+            Some(0) => Some(PlayerId::One),
+            Some(_) => Some(PlayerId::Two),
+            None => None,
+        }
+    }
+
+    /// Reads the events of the backend and returns the inputs of the paddles that changed.
+    pub fn poll(&mut self) -> Vec<(PlayerId, PaddleInput)> {
+        let mut inputs = Vec::new();
+        for event in self.backend.poll() {
+            match event {
+                GamepadEvent::Connected(gamepad) => {
+                    if !self.connected.contains(&gamepad) {
+                        self.connected.push(gamepad);
+                    }
+                    self.assign();
+                }
+                GamepadEvent::Disconnected(gamepad) => {
+                    self.connected.retain(|&id| id != gamepad);
+                    if let Some(player) = self.player(gamepad) {
+                        self.players[player.index()] = None;
+                        // Stop the paddle instead of letting it run with the last stick position
+                        self.set_input(player, PaddleInput::None, &mut inputs);
+                    }
+                    self.assign();
+                }
+                GamepadEvent::Axis(gamepad, position) => {
+                    if let Some(player) = self.player(gamepad) {
+                        self.set_input(player, stick_input(position), &mut inputs);
+                    }
+                }
+                GamepadEvent::SwitchSides(gamepad) => {
+                    if self.player(gamepad).is_some() {
+                        self.players.swap(0, 1);
+                        for player in [PlayerId::One, PlayerId::Two] {
+                            self.set_input(player, PaddleInput::None, &mut inputs);
+                        }
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    /// Gives the players without a gamepad the first connected gamepad nobody uses.
+    fn assign(&mut self) {
+        for player in 0..self.players.len() {
+            if self.players[player].is_none() {
+                self.players[player] = self
+                    .connected
+                    .iter()
+                    .copied()
+                    .find(|gamepad| !self.players.contains(&Some(*gamepad)));
+            }
+        }
+    }
+
+    fn set_input(
+        &mut self,
+        player: PlayerId,
+        input: PaddleInput,
+        inputs: &mut Vec<(PlayerId, PaddleInput)>,
+    ) {
+        let last = &mut self.inputs[player.index()];
+        if *last != input {
+            *last = input;
+            inputs.push((player, input));
+        }
+    }
+}
+
+/// The input of a stick position. Inside of the dead zone the paddle slows down like without input,
+/// outside of it the position is scaled so the paddle speeds up smoothly from a standstill.
+pub fn stick_input(position: f64) -> PaddleInput {
+    let distance = position.abs().min(1.0);
+    if distance < GAMEPAD_DEAD_ZONE {
+        return PaddleInput::None;
+    }
+    let scaled = (distance - GAMEPAD_DEAD_ZONE) / (1.0 - GAMEPAD_DEAD_ZONE);
+    PaddleInput::Analog(scaled * position.signum())
+}
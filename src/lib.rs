@@ -0,0 +1,355 @@
+// The original code of the game predates the use of clippy and is kept as it was written
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::needless_return,
+    clippy::neg_multiply,
+    clippy::swap_with_temporary,
+    clippy::too_many_arguments,
+    clippy::unnecessary_cast
+)]
+
+extern crate graphics;
+extern crate opengl_graphics;
+extern crate piston;
+
+use graphics::color::{BLACK, WHITE};
+use graphics::types::{Color, FontSize};
+use graphics::{clear, rectangle};
+use opengl_graphics::{GlGraphics, GlyphCache};
+use piston::{Key, RenderArgs};
+use rand::rngs::ThreadRng;
+
+use crate::audio::DefaultAudio;
+use crate::game_state::{
+    GameImpl, GameOptions, GameState, GameStateTrait, GraphicsImpl, GraphicsOptions,
+};
+use crate::viewport::CourtViewport;
+
+pub mod achievement;
+pub mod ai;
+pub mod arena;
+pub mod audio;
+pub mod ball;
+pub mod bench;
+pub mod config;
+pub mod debug;
+pub mod display;
+pub mod event;
+pub mod game_state;
+pub mod gamepad;
+pub mod paddle;
+pub mod profile;
+pub mod rating;
+pub mod replay;
+pub mod software;
+pub mod stats;
+pub mod synth;
+pub mod terminal;
+pub mod tournament;
+pub mod viewport;
+
+#[cfg(test)]
+mod test;
+
+/// Game implementation details
+pub struct DefaultGameImpl;
+impl GameImpl for DefaultGameImpl {
+    type Rng = ThreadRng;
+    type GraphicsImpl = DefaultGraphicsImpl;
+    type Audio = DefaultAudio;
+}
+
+pub struct DefaultGraphicsImpl;
+impl GraphicsImpl for DefaultGraphicsImpl {
+    type Graphics = GlGraphics;
+    type CharacterCache = GlyphCache<'static>;
+}
+
+/// The size of the window when the game starts.
+pub const WINDOW_SIZE: (u32, u32) = (1000, 500);
+/// The size of the court in logical units.
+/// Everything in the game is positioned in these units, no matter how big the window is.
+const COURT_SIZE: (u32, u32) = (1000, 500);
+
+/// The background color
+const BACKGROUND_COLOR: Color = BLACK;
+/// The color of the bars around the court if the window has a different aspect ratio.
+const LETTERBOX_COLOR: Color = [0.08, 0.08, 0.08, 1.0];
+
+/// The color of every paddle
+const PADDLE_COLOR: Color = WHITE;
+/// The gap between the window border with the smallest distance to the player.
+/// If it is the first player this is the left border and for the second player the right border.
+/// For more information on rendering a paddle look at [`render::render_paddle`]
+const PADDLE_BORDER_GAP: f64 = 50.0;
+/// The size of the paddle.
+/// For more information on rendering a paddle look at [`render::render_paddle`]
+const PADDLE_SIZE: (u32, u32) = (20, 50);
+
+/// The maximum speed of the paddle if the up or down button was pressed.
+const PADDLE_SPEED: f64 = 225.0;
+/// The acceleration of the paddle while the up or down button is pressed.
+const PADDLE_ACCELERATION: f64 = 1500.0;
+/// The deceleration of the paddle after the up or down button was released.
+const PADDLE_FRICTION: f64 = 1000.0;
+/// The fraction of the paddle velocity that is added to the y velocity of the ball when it is hit.
+const PADDLE_MOMENTUM_TRANSFER: f64 = 0.5;
+
+// Controls
+const BUTTON_PLAYER_1_UP: Key = Key::W;
+const BUTTON_PLAYER_1_DOWN: Key = Key::S;
+const BUTTON_PLAYER_2_UP: Key = Key::Up;
+const BUTTON_PLAYER_2_DOWN: Key = Key::Down;
+/// Returns to the arena editor while test playing an arena.
+const BUTTON_RETURN_TO_EDITOR: Key = Key::Tab;
+
+/// The color of the ball
+const BALL_COLOR: Color = WHITE;
+const BALL_SIZE: (u32, u32) = (10, 10);
+/// The starting velocity of the ball for the x axis.
+/// The y axis will be random between START_BALL_VELOCITY and -START_BALL_VELOCITY at the start
+const START_BALL_VELOCITY: f64 = 200.0;
+/// The multiplier of the ball velocity after it has hit a paddle.
+const BALL_MULTIPLIER: f64 = 1.1;
+/// The highest speed the ball can reach.
+/// Faster balls could move through a paddle in a single update.
+const BALL_MAX_SPEED: f64 = 800.0;
+/// The spin of the ball in radians per second for every unit of paddle velocity when hit by a paddle.
+const BALL_SPIN_TRANSFER: f64 = 0.02;
+/// How strong the spin of the ball bends its path.
+const BALL_MAGNUS: f64 = 0.15;
+/// The fraction of the spin the ball loses every second.
+const BALL_SPIN_DAMPING: f64 = 0.5;
+/// The x velocity the ball gets for every unit of spin when bouncing off the top or bottom.
+const BALL_WALL_SPIN_FRICTION: f64 = 5.0;
+/// The fraction of the spin the ball keeps after bouncing off the top or bottom.
+const BALL_WALL_SPIN_RETENTION: f64 = 0.5;
+
+/// The font used for the score and the countdown
+pub const FONT: &[u8] = include_bytes!("../roboto-font/Roboto-Regular.ttf");
+
+/// The color for the score of player one and two
+const SCORE_COLOR: Color = WHITE;
+/// The size of the score for player one and two
+const SCORE_SIZE: FontSize = 30;
+/// The gap between the top of the window and the score.
+/// Note that the text will be rendered from a bottom corner.
+const SCORE_Y_GAP: f64 = 50.0;
+
+/// The color of the obstacles of an arena.
+const OBSTACLE_COLOR: Color = [0.5, 0.5, 0.5, 1.0];
+/// The color of the goals of an arena.
+const GOAL_COLOR: Color = [0.2, 0.8, 0.2, 0.3];
+/// The size of the marker of a spawn point in the arena editor.
+const SPAWN_MARKER_SIZE: f64 = 10.0;
+/// The color of the spawn point of player one in the arena editor.
+const PLAYER_ONE_COLOR: Color = [0.2, 0.4, 1.0, 1.0];
+/// The color of the spawn point of player two in the arena editor.
+const PLAYER_TWO_COLOR: Color = [1.0, 0.3, 0.3, 1.0];
+
+// Arena editor
+/// The distance between two lines of the grid which objects snap to.
+const EDITOR_GRID_SIZE: f64 = 25.0;
+const EDITOR_GRID_COLOR: Color = [1.0, 1.0, 1.0, 0.1];
+/// The size of the handle used for resizing the selected object.
+const EDITOR_HANDLE_SIZE: f64 = 10.0;
+const EDITOR_SELECTION_COLOR: Color = [1.0, 0.8, 0.0, 1.0];
+const EDITOR_BUTTON_SIZE: (f64, f64) = (100.0, 30.0);
+const EDITOR_BUTTON_COLOR: Color = [0.25, 0.25, 0.25, 1.0];
+const EDITOR_TEXT_COLOR: Color = WHITE;
+const EDITOR_TEXT_SIZE: FontSize = 16;
+/// The default file the arena editor saves to.
+pub const EDITOR_DEFAULT_PATH: &str = "arena.json";
+
+// Arena editor controls
+const BUTTON_EDITOR_OBSTACLE: Key = Key::D1;
+const BUTTON_EDITOR_SPAWN: Key = Key::D2;
+const BUTTON_EDITOR_GOAL: Key = Key::D3;
+const BUTTON_EDITOR_GRID: Key = Key::G;
+const BUTTON_EDITOR_DELETE: Key = Key::Delete;
+const BUTTON_EDITOR_SAVE: Key = Key::S;
+const BUTTON_EDITOR_TEST_PLAY: Key = Key::T;
+
+// Breakout practice mode
+/// The lives at the start of a practice game.
+const BREAKOUT_LIVES: u32 = 3;
+/// The score for breaking a single brick, multiplied by the level.
+const BREAKOUT_BRICK_SCORE: u32 = 10;
+const BREAKOUT_BRICK_SIZE: (f64, f64) = (20.0, 45.0);
+/// The gap between two bricks.
+const BREAKOUT_BRICK_GAP: f64 = 5.0;
+const BREAKOUT_BRICK_COLOR: Color = [0.9, 0.5, 0.1, 1.0];
+/// The columns of bricks in the first level. Every level adds one more column.
+const BREAKOUT_START_COLUMNS: u32 = 2;
+const BREAKOUT_MAX_COLUMNS: u32 = 8;
+/// The multiplier of the ball velocity for every level after the first one.
+const BREAKOUT_LEVEL_SPEED_UP: f64 = 1.1;
+/// Starts a new practice game after the game is over.
+const BUTTON_BREAKOUT_RESTART: Key = Key::Space;
+
+// Menus
+const MENU_COLOR: Color = WHITE;
+const MENU_SELECTED_COLOR: Color = [1.0, 0.8, 0.0, 1.0];
+const MENU_TITLE_SIZE: FontSize = 40;
+const MENU_TEXT_SIZE: FontSize = 24;
+/// The vertical distance between two items of a menu.
+const MENU_ITEM_GAP: f64 = 40.0;
+const BUTTON_MENU_UP: Key = Key::Up;
+const BUTTON_MENU_DOWN: Key = Key::Down;
+const BUTTON_MENU_LEFT: Key = Key::Left;
+const BUTTON_MENU_RIGHT: Key = Key::Right;
+const BUTTON_MENU_SELECT: Key = Key::Return;
+const BUTTON_MENU_BACK: Key = Key::Backspace;
+
+/// The default maximum of frames rendered per second.
+const MAX_FPS: u64 = 60;
+/// The frame rate caps that can be chosen in the settings.
+const FPS_CAPS: [u64; 5] = [30, 60, 120, 144, 240];
+
+// Debug overlay
+const BUTTON_DEBUG_OVERLAY: Key = Key::F3;
+const BUTTON_DEBUG_PAUSE: Key = Key::F5;
+/// Pauses the game and advances it by a single update.
+const BUTTON_DEBUG_STEP: Key = Key::F6;
+const BUTTON_DEBUG_SLOWER: Key = Key::F7;
+const BUTTON_DEBUG_FASTER: Key = Key::F8;
+/// Goes back a single update, this also pauses the game.
+const BUTTON_DEBUG_REWIND: Key = Key::F9;
+/// Goes back [`DEBUG_REWIND_JUMP`] updates at once.
+const BUTTON_DEBUG_REWIND_JUMP: Key = Key::F10;
+/// The number of updates of a match that are kept for rewinding, 5 seconds at 120 updates per second.
+const DEBUG_REWIND_SNAPSHOTS: usize = 600;
+const DEBUG_REWIND_JUMP: usize = 120;
+/// The speeds of the game that can be chosen with the debug controls.
+const DEBUG_TIME_SCALES: [f64; 7] = [0.1, 0.25, 0.5, 1.0, 1.5, 2.0, 4.0];
+const DEBUG_TEXT_COLOR: Color = [1.0, 1.0, 0.0, 1.0];
+const DEBUG_TEXT_SIZE: FontSize = 14;
+/// The distance between the lines of text and to the top left corner.
+const DEBUG_TEXT_GAP: f64 = 20.0;
+const DEBUG_HITBOX_COLOR: Color = [0.0, 1.0, 0.0, 1.0];
+const DEBUG_VELOCITY_COLOR: Color = [1.0, 0.3, 0.3, 1.0];
+const DEBUG_INTERCEPT_COLOR: Color = [0.3, 0.6, 1.0, 1.0];
+const DEBUG_LINE_RADIUS: f64 = 1.0;
+/// The velocity arrow shows how far the ball moves in this many seconds.
+const DEBUG_VELOCITY_SCALE: f64 = 0.25;
+const DEBUG_ARROW_HEAD_SIZE: f64 = 8.0;
+
+// Computer players
+/// The time step used for moving the ball when predicting where it goes.
+const AI_PREDICTION_STEP: f64 = 1.0 / 120.0;
+/// How far into the future the path of the ball is predicted in seconds.
+const AI_PREDICTION_TIME: f64 = 5.0;
+/// A computer player stops moving its paddle when the center is this close to where it wants to be.
+const AI_DEAD_ZONE: f64 = 4.0;
+/// A linear controller doesn't move its paddle while the weighted sum is this close to zero.
+const AI_LINEAR_DEAD_ZONE: f64 = 0.05;
+
+// Instant replay
+/// The time of a rally that is shown again after a point in seconds.
+const INSTANT_REPLAY_TIME: f64 = 3.0;
+/// The speed of the instant replay, 1 is the normal speed.
+const INSTANT_REPLAY_SPEED: f64 = 0.4;
+const INSTANT_REPLAY_COLOR: Color = [1.0, 0.2, 0.2, 1.0];
+const INSTANT_REPLAY_TEXT_SIZE: FontSize = 40;
+const INSTANT_REPLAY_HINT_SIZE: FontSize = 16;
+const BUTTON_INSTANT_REPLAY_SKIP: Key = Key::Space;
+
+// Profiles
+/// The most characters in the name of a profile.
+const PROFILE_NAME_LENGTH: usize = 16;
+const BUTTON_PROFILE_SHIFT: [Key; 2] = [Key::LShift, Key::RShift];
+/// The points to win a versus match if the config has no points to win, so the match counts for the profiles.
+const VERSUS_POINTS_TO_WIN: u32 = 11;
+
+// Ratings
+/// The rating of a player before the first rated match.
+const RATING_INITIAL: f64 = 1500.0;
+/// The most a rating changes with a single match.
+const RATING_K_FACTOR: f64 = 32.0;
+
+// Match summary
+const SUMMARY_TEXT_SIZE: FontSize = 20;
+/// The gap between two rows of the statistics table.
+const SUMMARY_ROW_GAP: f64 = 32.0;
+/// The x positions of the names and of the values of player one and two.
+const SUMMARY_COLUMNS: [f64; 3] = [200.0, 480.0, 680.0];
+const BUTTON_SUMMARY_CONTINUE: Key = Key::Return;
+
+// Leaderboard
+/// The most players shown on the leaderboard.
+const LEADERBOARD_ROWS: usize = 8;
+/// The x positions of the rank, name, rating, wins and losses.
+const LEADERBOARD_COLUMNS: [f64; 5] = [160.0, 210.0, 500.0, 620.0, 720.0];
+
+// Achievements
+/// The time in seconds a toast about an unlocked achievement is shown.
+const ACHIEVEMENT_TOAST_TIME: f64 = 3.0;
+const ACHIEVEMENT_TOAST_SIZE: (f64, f64) = (440.0, 64.0);
+const ACHIEVEMENT_TOAST_COLOR: Color = [0.15, 0.15, 0.15, 0.9];
+const ACHIEVEMENT_TEXT_SIZE: FontSize = 16;
+/// Speeds this close to the speed of an achievement count, a ball at the max speed is only exact up to rounding.
+const ACHIEVEMENT_SPEED_TOLERANCE: f64 = 0.5;
+
+// Gamepads
+/// Stick positions closer to the center than this are ignored, worn sticks rarely rest at exactly 0.
+const GAMEPAD_DEAD_ZONE: f64 = 0.15;
+
+// Benchmark of computer players
+/// The matches between every pair of controllers, half of them on each side.
+const BENCH_MATCHES: u32 = 20;
+/// Shorter than a normal match, so more matches fit into the same time.
+const BENCH_POINTS_TO_WIN: u32 = 5;
+/// The time step of the simulation in seconds.
+const BENCH_STEP: f64 = 1.0 / 120.0;
+/// The longest a match is played in seconds, two controllers that never miss would play forever.
+const BENCH_MAX_MATCH_TIME: f64 = 600.0;
+/// The z score of the confidence intervals, 1.96 for 95%.
+const BENCH_CONFIDENCE_Z: f64 = 1.96;
+
+// Tournaments
+const TOURNAMENT_MIN_PLAYERS: usize = 4;
+const TOURNAMENT_MAX_PLAYERS: usize = 16;
+/// The points to win a tournament match if the config has no points to win, a tournament needs matches that end.
+const TOURNAMENT_POINTS_TO_WIN: u32 = 11;
+const TOURNAMENT_TEXT_SIZE: FontSize = 16;
+/// The gap between two matches or players on the bracket.
+const TOURNAMENT_ROW_GAP: f64 = 22.0;
+/// The x positions of the matches of the round and of the standings.
+const TOURNAMENT_COLUMNS: [f64; 2] = [100.0, 620.0];
+/// The color of players that are out of the tournament.
+const TOURNAMENT_ELIMINATED_COLOR: Color = [0.5, 0.5, 0.5, 1.0];
+
+// Audio
+/// The samples per second of the sound effects.
+const AUDIO_SAMPLE_RATE: u32 = 44_100;
+/// The loudness of the sound effects from 0 to 1.
+const AUDIO_VOLUME: f32 = 0.25;
+/// The frequency of a paddle hit in hertz when the ball has its starting speed.
+const SYNTH_HIT_FREQUENCY: f64 = 330.0;
+
+/// The countdown after someone has scored in seconds.
+const SCORE_COUNTDOWN: f64 = 4.0;
+/// Color for the countdown after someone has scored.
+const SCORE_COUNTDOWN_COLOR: Color = WHITE;
+/// Font size for the countdown after someone has scored.
+const SCORE_COUNTDOWN_SIZE: FontSize = 30;
+
+/// Renders the state into the court with bars around it if the aspect ratio doesn't match.
+/// Used by the window and by the software renderer, so both show the same picture.
+pub fn render_court<Impl: GameImpl>(
+    state: &mut GameState,
+    ctx: &mut GraphicsOptions<Impl::GraphicsImpl>,
+    args: &RenderArgs,
+    options: &mut GameOptions<Impl>,
+) {
+    let viewport = CourtViewport::new(args.window_size);
+    clear(LETTERBOX_COLOR, ctx.graphics);
+
+    // Draw everything in court coordinates and cut off what is outside of the court
+    ctx.ctx.draw_state = ctx.ctx.draw_state.scissor(viewport.scissor(args));
+    ctx.ctx.transform = viewport.transform(ctx.ctx.transform);
+    let court = [0.0, 0.0, COURT_SIZE.0 as f64, COURT_SIZE.1 as f64];
+    rectangle(BACKGROUND_COLOR, court, ctx.ctx.transform, ctx.graphics);
+
+    state.render(ctx, args, options);
+}
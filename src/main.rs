@@ -1,106 +1,43 @@
 extern crate glutin_window;
-extern crate graphics;
 extern crate opengl_graphics;
 extern crate piston;
 
-
 use glutin_window::GlutinWindow as Window;
-use graphics::color::{BLACK, WHITE};
-use graphics::types::{Color, FontSize};
-use graphics::{clear};
 use opengl_graphics::{GlGraphics, GlyphCache, OpenGL, TextureSettings};
-
 use piston::{
-    Button, EventSettings, Events, Key, PressEvent, ReleaseEvent, RenderArgs,
-    RenderEvent, UpdateArgs, UpdateEvent, WindowSettings,
+    Button, EventLoop, EventSettings, Events, MouseCursorEvent, PressEvent, ReleaseEvent,
+    RenderArgs, RenderEvent, UpdateArgs, UpdateEvent, WindowSettings,
 };
-use rand::rngs::ThreadRng;
-use rand::{thread_rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, SeedableRng};
+use std::env;
 use std::mem::swap;
-
+use std::path::PathBuf;
 use std::time::Duration;
 
-
-use crate::game_state::countdown::CountdownState;
-use crate::game_state::play::PlayState;
-use crate::game_state::{
-    GameImpl, GameOptions, GameState, GameStateTrait, GraphicsImpl, GraphicsOptions, Invalid,
+use pong_rs::achievement::Achievements;
+use pong_rs::arena::Arena;
+use pong_rs::audio::{DefaultAudio, NullAudio};
+use pong_rs::config::{Config, DisplayMode};
+use pong_rs::debug::DebugOverlay;
+use pong_rs::display;
+use pong_rs::event::EventQueue;
+use pong_rs::game_state::breakout::BreakoutState;
+use pong_rs::game_state::countdown::CountdownState;
+use pong_rs::game_state::editor::EditorState;
+use pong_rs::game_state::play::PlayState;
+use pong_rs::game_state::{
+    GameImpl, GameOptions, GameState, GameStateTrait, GraphicsOptions, Invalid,
 };
-
-
-pub mod ball;
-pub mod game_state;
-pub mod paddle;
-
-#[cfg(test)]
-mod test;
-
-/// Game implementation details
-struct DefaultGameImpl;
-impl GameImpl for DefaultGameImpl {
-    type Rng = ThreadRng;
-    type GraphicsImpl = DefaultGraphicsImpl;
-}
+use pong_rs::gamepad::{DefaultGamepads, Gamepads};
+use pong_rs::profile::ProfileStore;
+use pong_rs::replay::Replay;
+use pong_rs::software::{HeadlessGameImpl, SoftwareRenderer};
+use pong_rs::viewport::CourtViewport;
+use pong_rs::{render_court, DefaultGameImpl, EDITOR_DEFAULT_PATH, FONT, WINDOW_SIZE};
 
 /// The OpenGL version to use.
 const OPENGL_VERSION: OpenGL = OpenGL::V3_2;
-struct DefaultGraphicsImpl;
-impl GraphicsImpl for DefaultGraphicsImpl {
-    type Graphics = GlGraphics;
-    type CharacterCache = GlyphCache<'static>;
-}
-
-/// The size of the window.
-const WINDOW_SIZE: (u32, u32) = (1000, 500);
-
-/// The background color
-const BACKGROUND_COLOR: Color = BLACK;
-
-/// The color of every paddle
-const PADDLE_COLOR: Color = WHITE;
-/// The gap between the window border with the smallest distance to the player.
-/// If it is the first player this is the left border and for the second player the right border.
-/// For more information on rendering a paddle look at [`render::render_paddle`]
-const PADDLE_BORDER_GAP: f64 = 50.0;
-/// The size of the paddle.
-/// For more information on rendering a paddle look at [`render::render_paddle`]
-const PADDLE_SIZE: (u32, u32) = (20, 50);
-
-/// The speed of the paddle if the up or down button was pressed.
-const PADDLE_SPEED: f64 = 225.0;
-
-// Controls
-const BUTTON_PLAYER_1_UP: Key = Key::W;
-const BUTTON_PLAYER_1_DOWN: Key = Key::S;
-const BUTTON_PLAYER_2_UP: Key = Key::Up;
-const BUTTON_PLAYER_2_DOWN: Key = Key::Down;
-
-/// The color of the ball
-const BALL_COLOR: Color = WHITE;
-const BALL_SIZE: (u32, u32) = (10, 10);
-/// The starting velocity of the ball for the x axis.
-/// The y axis will be random between START_BALL_VELOCITY and -START_BALL_VELOCITY at the start
-const START_BALL_VELOCITY: f64 = 200.0;
-/// The multiplier of the ball velocity after it has hit a paddle.
-const BALL_MULTIPLIER: f64 = 1.1;
-
-/// The font used for the score and the countdown
-const FONT: &[u8] = include_bytes!("../roboto-font/Roboto-Regular.ttf");
-
-/// The color for the score of player one and two
-const SCORE_COLOR: Color = WHITE;
-/// The size of the score for player one and two
-const SCORE_SIZE: FontSize = 30;
-/// The gap between the top of the window and the score.
-/// Note that the text will be rendered from a bottom corner.
-const SCORE_Y_GAP: f64 = 50.0;
-
-/// The countdown after someone has scored in seconds.
-const SCORE_COUNTDOWN: f64 = 4.0;
-/// Color for the countdown after someone has scored.
-const SCORE_COUNTDOWN_COLOR: Color = WHITE;
-/// Font size for the countdown after someone has scored.
-const SCORE_COUNTDOWN_SIZE: FontSize = 30;
 
 struct Game {
     graphics: GlGraphics,
@@ -108,61 +45,216 @@ struct Game {
 
     options: GameOptions<DefaultGameImpl>,
     state: GameState,
+    /// The viewport of the last render, used to map the mouse cursor into the court.
+    viewport: CourtViewport,
+    /// Records every update of a match if the game was started with --record.
+    replay: Option<Replay>,
+    debug: DebugOverlay,
+    gamepads: Gamepads<DefaultGamepads>,
 }
 
 impl Game {
     fn update(&mut self, args: &UpdateArgs) {
-        let mut state = GameState::Invalid(Box::new(Invalid));
-        swap(&mut self.state, &mut state);
+        for (player, input) in self.gamepads.poll() {
+            self.state.set_paddle_input(&player, input);
+        }
 
-        match state.update(args, &mut self.options) {
-            Ok(state) => self.state = state,
-            Err(state) => self.state = state,
+        // The debug controls can pause, slow down or speed up the game
+        for _ in 0..self.debug.time.updates() {
+            let mut state = GameState::Invalid(Box::new(Invalid));
+            swap(&mut self.state, &mut state);
+
+            match state.update(args, &mut self.options) {
+                Ok(state) => self.state = state,
+                Err(state) => self.state = state,
+            }
+
+            if let Some(replay) = &mut self.replay {
+                replay.record(&self.state, args.dt);
+            }
+            let GameOptions {
+                events,
+                audio,
+                profiles,
+                achievements,
+                ..
+            } = &mut self.options;
+            events.dispatch(args.dt, &mut [audio, profiles, achievements]);
+            achievements.unlock_reached(profiles);
+            self.debug.update(&self.state);
         }
     }
 
     fn render(&mut self, args: &RenderArgs) {
-        self.graphics.draw(args.viewport(), |mut context, gl| {
-            clear(BACKGROUND_COLOR, gl);
+        self.viewport = CourtViewport::new(args.window_size);
 
+        self.graphics.draw(args.viewport(), |mut context, gl| {
             let mut graphic_options = GraphicsOptions {
                 graphics: gl,
                 character_cache: &mut self.character_cache,
                 ctx: &mut context,
             };
-            self.state
-                .render(&mut graphic_options, args, &mut self.options);
+            render_court(
+                &mut self.state,
+                &mut graphic_options,
+                args,
+                &mut self.options,
+            );
+            self.debug
+                .render(&self.state, &mut graphic_options, &self.options);
         });
+        self.debug.count_frame();
     }
 
     fn button_press(&mut self, button: &Button) {
+        self.debug.button_press(button, &mut self.state);
         self.state.button_press(button, &self.options);
     }
     fn button_release(&mut self, button: &Button) {
         self.state.button_release(button, &self.options);
     }
+    fn mouse_move(&mut self, position: &[f64; 2]) {
+        if let Some(position) = self.viewport.window_to_court(*position) {
+            self.state.mouse_move(&position, &self.options);
+        }
+    }
+}
+
+/// The command line arguments of the game.
+#[derive(Default)]
+struct Arguments {
+    /// The arena to play in.
+    arena: Option<PathBuf>,
+    /// Opens the arena editor for this file instead of starting a game.
+    edit: Option<PathBuf>,
+    /// Starts the single player breakout practice mode.
+    practice: bool,
+    /// Overrides the display mode of the config file.
+    display_mode: Option<DisplayMode>,
+    /// Renders the first frame into this PNG file without opening a window.
+    screenshot: Option<PathBuf>,
+    /// Records the matches into this replay file, which is written when the game is closed.
+    record: Option<PathBuf>,
+    /// Enables the debug controls to pause, change the speed and rewind.
+    /// They are always enabled in debug builds.
+    debug: bool,
+}
+
+impl Arguments {
+    fn parse() -> Self {
+        let mut arguments = Self::default();
+
+        let mut args = env::args().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--arena" => {
+                    let path = args.next().expect("Missing the path after --arena!");
+                    arguments.arena = Some(PathBuf::from(path));
+                }
+                "--edit" => {
+                    let path = args
+                        .next_if(|arg| !arg.starts_with("--"))
+                        .unwrap_or_else(|| EDITOR_DEFAULT_PATH.to_string());
+                    arguments.edit = Some(PathBuf::from(path));
+                }
+                "--practice" => arguments.practice = true,
+                "--fullscreen" => {
+                    let name = args.next_if(|arg| !arg.starts_with("--"));
+                    let mode =
+                        DisplayMode::from_fullscreen_arg(name.as_deref()).unwrap_or_else(|| {
+                            panic!("Unknown display mode {}!", name.unwrap_or_default())
+                        });
+                    arguments.display_mode = Some(mode);
+                }
+                "--screenshot" => {
+                    let path = args.next().expect("Missing the path after --screenshot!");
+                    arguments.screenshot = Some(PathBuf::from(path));
+                }
+                "--record" => {
+                    let path = args.next().expect("Missing the path after --record!");
+                    arguments.record = Some(PathBuf::from(path));
+                }
+                "--debug" => arguments.debug = true,
+                _ => panic!("Unknown argument {}!", arg),
+            }
+        }
+
+        arguments
+    }
 }
 
 fn main() {
+    let arguments = Arguments::parse();
+    let mut config = Config::load_or_default();
+    // The display mode given on the command line is only used for this run and never saved
+    config.display.mode_override = arguments.display_mode;
+
+    if let Some(path) = &arguments.screenshot {
+        let mut options: GameOptions<HeadlessGameImpl> = GameOptions {
+            rng: StdRng::from_entropy(),
+            config,
+            audio: NullAudio,
+            events: EventQueue::new(),
+            profiles: ProfileStore::in_memory(),
+            achievements: Achievements::default(),
+        };
+        let mut state = create_start_state(&arguments, &mut options);
+
+        let mut renderer = SoftwareRenderer::new(WINDOW_SIZE.0, WINDOW_SIZE.1);
+        renderer.render(&mut state, &mut options);
+        renderer
+            .graphics
+            .save_png(path)
+            .expect("Unable to save the screenshot!");
+        return;
+    }
+
+    let mut display = config.display.clone();
+
     let mut window: Window = WindowSettings::new("Pong", WINDOW_SIZE)
         .graphics_api(OPENGL_VERSION)
-        .resizable(false)
+        .resizable(true)
+        .vsync(display.vsync)
         .exit_on_esc(true)
         .build()
         .expect("Unable to create Glutin Window!");
+    display::set_display_mode(&window, display.current_mode());
+
+    let mut options: GameOptions<DefaultGameImpl> = GameOptions {
+        rng: thread_rng(),
+        config,
+        audio: DefaultAudio::default(),
+        events: EventQueue::new(),
+        profiles: ProfileStore::load_default(),
+        achievements: Achievements::load_or_default(),
+    };
 
-    let mut options: GameOptions<DefaultGameImpl> = GameOptions { rng: thread_rng() };
+    let mut debug = DebugOverlay::new();
+    debug.controls = arguments.debug || cfg!(debug_assertions);
 
     let mut game = Game {
         graphics: GlGraphics::new(OPENGL_VERSION),
         character_cache: GlyphCache::from_bytes(FONT, (), TextureSettings::new())
             .expect("Unable to create font!"),
-        state: create_start_state(&mut options),
+        state: create_start_state(&arguments, &mut options),
         options,
+        viewport: CourtViewport::new([WINDOW_SIZE.0 as f64, WINDOW_SIZE.1 as f64]),
+        replay: arguments.record.as_ref().map(|_| Replay::default()),
+        debug,
+        gamepads: Gamepads::new(DefaultGamepads::default()),
     };
 
-    let mut events = Events::new(EventSettings::new());
+    let mut events = Events::new(EventSettings::new().max_fps(display.max_fps));
     while let Some(event) = events.next(&mut window) {
+        // Apply changes of the display settings
+        if game.options.config.display != display {
+            if game.options.config.display.current_mode() != display.current_mode() {
+                display::set_display_mode(&window, game.options.config.display.current_mode());
+            }
+            events.set_max_fps(game.options.config.display.max_fps);
+            display = game.options.config.display.clone();
+        }
+
         if let Some(render_args) = event.render_args() {
             game.render(&render_args);
         }
@@ -177,12 +269,40 @@ fn main() {
         if let Some(button) = event.release_args() {
             game.button_release(&button);
         }
+        if let Some(position) = event.mouse_cursor_args() {
+            game.mouse_move(&position);
+        }
+    }
+
+    if let (Some(path), Some(replay)) = (&arguments.record, &game.replay) {
+        replay.save(path).expect("Unable to save the replay!");
     }
 }
 
-fn create_start_state(options: &mut GameOptions<DefaultGameImpl>) -> GameState {
+fn create_start_state<Impl: GameImpl>(
+    arguments: &Arguments,
+    options: &mut GameOptions<Impl>,
+) -> GameState {
+    if let Some(path) = &arguments.edit {
+        // Start with an empty arena if the file doesn't exist yet
+        let arena = Arena::load_or_default(path).expect("Unable to load the arena!");
+        return GameState::Editor(Box::new(EditorState::new(arena, path.clone())));
+    }
+
+    if arguments.practice {
+        return GameState::Countdown(Box::new(CountdownState::new(
+            Duration::from_secs(3),
+            GameState::Breakout(Box::new(BreakoutState::new(options))),
+        )));
+    }
+
+    let arena = match &arguments.arena {
+        Some(path) => Arena::load(path).expect("Unable to load the arena!"),
+        None => return GameState::Menu(Box::default()),
+    };
+
     GameState::Countdown(Box::new(CountdownState::new(
         Duration::from_secs(3),
-        GameState::Play(Box::new(PlayState::new(options))),
+        GameState::Play(Box::new(PlayState::with_arena(options, arena))),
     )))
 }
@@ -3,16 +3,22 @@ use std::ops::Range;
 
 use graphics::{rectangle, Context, Graphics};
 use piston::UpdateArgs;
+use serde::{Deserialize, Serialize};
 
 use crate::ball::Ball;
+use crate::config::PaddleConfig;
 use crate::game_state::play::PlayState;
-use crate::{BALL_SIZE, PADDLE_COLOR, PADDLE_SIZE, PADDLE_SPEED};
+use crate::{BALL_SIZE, PADDLE_COLOR, PADDLE_SIZE};
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PaddleInput {
     Up,
     Down,
     UpDown,
     None,
+    /// The position of a gamepad stick from -1 (down) to 1 (up),
+    /// the paddle speeds up or slows down to the same share of its max speed.
+    Analog(f64),
 }
 
 impl PaddleInput {
@@ -43,9 +49,11 @@ impl PaddleInput {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Paddle {
     x: f64,
     y: f64,
+    velocity: f64,
     input: PaddleInput,
 }
 
@@ -54,6 +62,7 @@ impl Paddle {
         Self {
             x,
             y,
+            velocity: 0.0,
             input: PaddleInput::None,
         }
     }
@@ -64,6 +73,10 @@ impl Paddle {
     pub fn y(&self) -> f64 {
         self.y
     }
+    /// The vertical velocity of the paddle. Positive values move the paddle up.
+    pub fn velocity(&self) -> f64 {
+        self.velocity
+    }
     pub fn set_y(&mut self, y: f64) {
         self.y = y;
     }
@@ -88,17 +101,49 @@ impl Paddle {
         &mut self.input
     }
 
-    /// Updates the paddle by moving it in the direction the input is.
-    pub fn update(&mut self, update_args: &UpdateArgs, mut y_range: Range<f64>) {
+    /// Updates the paddle by accelerating it in the direction the input is.
+    /// An analog input accelerates it towards the velocity of the stick position.
+    /// Without any input the paddle slows down because of the friction.
+    pub fn update(
+        &mut self,
+        update_args: &UpdateArgs,
+        mut y_range: Range<f64>,
+        config: &PaddleConfig,
+    ) {
         // Subtract the paddle height from the range so that the paddle won't go off the screen
         y_range.end -= PADDLE_SIZE.1 as f64;
 
-        let change = match self.input {
-            PaddleInput::Up => PADDLE_SPEED * (update_args.dt),
-            PaddleInput::Down => -PADDLE_SPEED * (update_args.dt),
+        let direction = match self.input {
+            PaddleInput::Up => 1.0,
+            PaddleInput::Down => -1.0,
             _ => 0.0,
         };
-        self.change_y(change, y_range);
+
+        if let PaddleInput::Analog(position) = self.input {
+            let target = position.clamp(-1.0, 1.0) * config.max_speed;
+            let change = config.acceleration * update_args.dt;
+            self.velocity += (target - self.velocity).clamp(-change, change);
+        } else if direction != 0.0 {
+            self.velocity += direction * config.acceleration * update_args.dt;
+            self.velocity = self.velocity.clamp(-config.max_speed, config.max_speed);
+        } else {
+            // Slow the paddle down without letting it go into the other direction
+            let slow_down = config.friction * update_args.dt;
+            if self.velocity.abs() <= slow_down {
+                self.velocity = 0.0;
+            } else {
+                self.velocity -= slow_down * self.velocity.signum();
+            }
+        }
+
+        self.change_y(self.velocity * update_args.dt, y_range.clone());
+
+        // Stop the paddle if it hit the top or bottom
+        if (self.y <= y_range.start && self.velocity < 0.0)
+            || (self.y >= y_range.end && self.velocity > 0.0)
+        {
+            self.velocity = 0.0;
+        }
     }
 
     /// Renders the paddle.
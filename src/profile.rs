@@ -0,0 +1,374 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Difficulty;
+use crate::event::{EventSubscriber, GameEvent};
+use crate::game_state::play::PlayerId;
+use crate::rating::{append_history, load_history, Ratings};
+use crate::PROFILE_NAME_LENGTH;
+
+/// The directory of the profiles inside of the data directory of the user.
+const PROFILE_DIR: &str = "pong-rs/profiles";
+/// The file inside of the profile directory with every finished match, one per line.
+const HISTORY_FILE: &str = "history.jsonl";
+/// The name used for a player without a profile.
+pub const GUEST: &str = "Guest";
+
+/// The wins and losses against a single opponent.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Record {
+    pub wins: u32,
+    pub losses: u32,
+}
+
+/// A named local player with the stats of all matches played with it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub points_won: u32,
+    pub points_lost: u32,
+    /// The most hits in a single rally of any match.
+    pub longest_rally: u32,
+    /// The records against every opponent by their name, players without a profile count as guests.
+    pub head_to_head: BTreeMap<String, Record>,
+    /// The ids of the unlocked achievements.
+    pub achievements: BTreeSet<String>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the name can be used for a new profile.
+    /// Names are also the file names, so they only contain letters, digits and spaces.
+    /// The names of guests and computer players are reserved.
+    pub fn is_valid_name(name: &str) -> bool {
+        let length = name.chars().count();
+        let reserved = Difficulty::ALL
+            .iter()
+            .map(|difficulty| difficulty.name())
+            .chain([GUEST])
+            .any(|reserved| name.eq_ignore_ascii_case(reserved));
+        (1..=PROFILE_NAME_LENGTH).contains(&length)
+            && name.trim() == name
+            && !reserved
+            && name
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == ' ')
+    }
+
+    /// Adds a finished match from the view of the player.
+    pub fn record_match(&mut self, result: &MatchResult, player: &PlayerId) {
+        let (opponent, points_won, points_lost) = match player {
+            PlayerId::One => (PlayerId::Two, result.score.0, result.score.1),
+            PlayerId::Two => (PlayerId::One, result.score.1, result.score.0),
+        };
+        let record = self
+            .head_to_head
+            .entry(result.name(&opponent).to_string())
+            .or_default();
+        if result.winner == *player {
+            self.wins += 1;
+            record.wins += 1;
+        } else {
+            self.losses += 1;
+            record.losses += 1;
+        }
+
+        self.points_won += points_won;
+        self.points_lost += points_lost;
+        self.longest_rally = self.longest_rally.max(result.longest_rally);
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = serde_json::to_string_pretty(self)?;
+        fs::write(path, file)
+    }
+}
+
+/// Who plays on one side of a match.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Participant {
+    /// A human player without a profile.
+    #[default]
+    Guest,
+    Profile(String),
+    Bot(Difficulty),
+}
+
+impl Participant {
+    /// The name of the profile, the computer player or [`GUEST`].
+    pub fn name(&self) -> &str {
+        match self {
+            Participant::Guest => GUEST,
+            Participant::Profile(name) => name,
+            Participant::Bot(difficulty) => difficulty.name(),
+        }
+    }
+}
+
+/// The outcome of a finished match.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchResult {
+    /// Player one and two.
+    pub players: [Participant; 2],
+    pub winner: PlayerId,
+    /// The final score of player one and player two.
+    pub score: (u32, u32),
+    /// The most hits in a single rally of the match.
+    pub longest_rally: u32,
+}
+
+impl MatchResult {
+    pub fn player(&self, player: &PlayerId) -> &Participant {
+        match player {
+            PlayerId::One => &self.players[0],
+            PlayerId::Two => &self.players[1],
+        }
+    }
+
+    /// The name of the player, see [`Participant::name`].
+    pub fn name(&self, player: &PlayerId) -> &str {
+        self.player(player).name()
+    }
+}
+
+/// All profiles, kept in a directory with one file for every profile,
+/// and the history of all matches with the ratings computed from it.
+#[derive(Debug, Default)]
+pub struct ProfileStore {
+    /// The directory the profiles are saved in, None keeps them only in memory.
+    dir: Option<PathBuf>,
+    /// Sorted by name.
+    profiles: Vec<Profile>,
+    /// Every finished match, oldest first.
+    history: Vec<MatchResult>,
+    ratings: Ratings,
+}
+
+impl ProfileStore {
+    /// A store that is never saved, for tests and games without a data directory.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// The location of the profiles.
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(PROFILE_DIR))
+    }
+
+    /// Loads every profile of the directory.
+    /// A broken profile file is reported and renamed, so the rest of the profiles can still be used
+    /// and nothing is lost when a new profile with the same name is saved.
+    pub fn load(dir: &Path) -> Self {
+        let history = load_history(&dir.join(HISTORY_FILE));
+        let mut store = Self {
+            dir: Some(dir.to_path_buf()),
+            profiles: Vec::new(),
+            ratings: Ratings::compute(&history),
+            history,
+        };
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Unable to read the profiles in {}: {}", dir.display(), err);
+                }
+                return store;
+            }
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            match Profile::load(&path) {
+                Ok(profile) if Profile::is_valid_name(&profile.name) => {
+                    store.profiles.push(profile)
+                }
+                Ok(profile) => eprintln!(
+                    "Skipping the profile {} with the invalid name {:?}",
+                    path.display(),
+                    profile.name
+                ),
+                Err(err) => {
+                    let broken = broken_path(&path);
+                    match fs::rename(&path, &broken) {
+                        Ok(()) => eprintln!(
+                            "Unable to load the profile {}, moved it to {}: {}",
+                            path.display(),
+                            broken.display(),
+                            err
+                        ),
+                        Err(rename_err) => eprintln!(
+                            "Unable to load the profile {}: {}, and unable to move it to {}: {}",
+                            path.display(),
+                            err,
+                            broken.display(),
+                            rename_err
+                        ),
+                    }
+                }
+            }
+        }
+        store.profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        store
+    }
+
+    /// Loads the profiles from the data directory of the user, without profiles if there is none.
+    pub fn load_default() -> Self {
+        match Self::path() {
+            Some(dir) => Self::load(&dir),
+            None => Self::in_memory(),
+        }
+    }
+
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    /// Every finished match, oldest first.
+    pub fn history(&self) -> &[MatchResult] {
+        &self.history
+    }
+
+    /// The ratings of all players, computed from the history.
+    pub fn ratings(&self) -> &Ratings {
+        &self.ratings
+    }
+
+    /// Everyone that can be chosen to play, the profiles followed by the computer players.
+    pub fn participants(&self) -> Vec<Participant> {
+        self.profiles
+            .iter()
+            .map(|profile| Participant::Profile(profile.name.clone()))
+            .chain(Difficulty::ALL.map(Participant::Bot))
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Adds and saves a new profile.
+    pub fn create(&mut self, name: &str) -> io::Result<()> {
+        if !Profile::is_valid_name(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?} is not a valid name", name),
+            ));
+        }
+        // Names that only differ in case would share a file on some systems
+        if self
+            .profiles
+            .iter()
+            .any(|profile| profile.name.eq_ignore_ascii_case(name))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("There already is a profile named {}", name),
+            ));
+        }
+
+        let profile = Profile::new(name);
+        self.save(&profile)?;
+        let index = self
+            .profiles
+            .partition_point(|other| other.name < profile.name);
+        self.profiles.insert(index, profile);
+        Ok(())
+    }
+
+    /// Adds the match to the history and to the profiles of both players and saves them.
+    pub fn record_match(&mut self, result: &MatchResult) -> io::Result<()> {
+        self.history.push(result.clone());
+        self.ratings.record(result);
+        if let Some(dir) = &self.dir {
+            append_history(&dir.join(HISTORY_FILE), result)?;
+        }
+
+        for player in [PlayerId::One, PlayerId::Two] {
+            let Participant::Profile(name) = result.player(&player) else {
+                continue;
+            };
+            let Some(index) = self
+                .profiles
+                .iter()
+                .position(|profile| profile.name == *name)
+            else {
+                continue;
+            };
+            self.profiles[index].record_match(result, &player);
+            self.save(&self.profiles[index])?;
+        }
+        Ok(())
+    }
+
+    /// Unlocks and saves an achievement of the profile.
+    /// Returns false if it was unlocked before or there is no such profile.
+    pub fn unlock(&mut self, name: &str, achievement: &str) -> io::Result<bool> {
+        let Some(index) = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+        else {
+            return Ok(false);
+        };
+        if !self.profiles[index]
+            .achievements
+            .insert(achievement.to_string())
+        {
+            return Ok(false);
+        }
+        self.save(&self.profiles[index])?;
+        Ok(true)
+    }
+
+    fn save(&self, profile: &Profile) -> io::Result<()> {
+        match &self.dir {
+            Some(dir) => profile.save(&dir.join(format!("{}.json", profile.name))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl EventSubscriber for ProfileStore {
+    fn handle(&mut self, event: &GameEvent) {
+        if let GameEvent::MatchOver(result) = event {
+            if let Err(err) = self.record_match(result) {
+                eprintln!("Unable to save the profiles: {}", err);
+            }
+        }
+    }
+}
+
+/// Where a broken profile file is moved to, without replacing the file of an earlier broken profile.
+fn broken_path(path: &Path) -> PathBuf {
+    let mut broken = path.with_extension("json.broken");
+    let mut number = 1;
+    while broken.exists() {
+        broken = path.with_extension(format!("json.broken.{}", number));
+        number += 1;
+    }
+    broken
+}
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::ai::Difficulty;
+use crate::game_state::play::PlayerId;
+use crate::profile::{MatchResult, Participant};
+use crate::{RATING_INITIAL, RATING_K_FACTOR};
+
+/// The Elo rating of a player with the matches it is based on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+impl Rating {
+    fn new(rating: f64) -> Self {
+        Self {
+            rating,
+            wins: 0,
+            losses: 0,
+        }
+    }
+}
+
+/// The chance of a player with the rating to win against an opponent with the other rating.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Elo ratings of the profiles and computer players.
+/// They only depend on the order of the matches, so the same history always gives the same ratings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ratings {
+    ratings: BTreeMap<Participant, Rating>,
+}
+
+impl Default for Ratings {
+    fn default() -> Self {
+        // The computer players are rated from the start, so there always is an opponent to recommend
+        let ratings = Difficulty::ALL
+            .iter()
+            .map(|difficulty| {
+                (
+                    Participant::Bot(*difficulty),
+                    Rating::new(difficulty.initial_rating()),
+                )
+            })
+            .collect();
+        Self { ratings }
+    }
+}
+
+impl Ratings {
+    /// Rates every match of the history in order.
+    pub fn compute(history: &[MatchResult]) -> Self {
+        let mut ratings = Self::default();
+        for result in history {
+            ratings.record(result);
+        }
+        ratings
+    }
+
+    /// Updates the ratings of both players after the match.
+    /// Matches with a guest are not rated, because a guest can be anyone.
+    pub fn record(&mut self, result: &MatchResult) {
+        let [one, two] = &result.players;
+        if *one == Participant::Guest || *two == Participant::Guest || one == two {
+            return;
+        }
+
+        let rating_one = self.rating(one);
+        let rating_two = self.rating(two);
+        let score_one = match result.winner {
+            PlayerId::One => 1.0,
+            PlayerId::Two => 0.0,
+        };
+        let change = RATING_K_FACTOR * (score_one - expected_score(rating_one, rating_two));
+
+        for (participant, rating, change, won) in [
+            (one, rating_one, change, score_one == 1.0),
+            (two, rating_two, -change, score_one == 0.0),
+        ] {
+            let entry = self
+                .ratings
+                .entry(participant.clone())
+                .or_insert_with(|| Rating::new(rating));
+            entry.rating = rating + change;
+            if won {
+                entry.wins += 1;
+            } else {
+                entry.losses += 1;
+            }
+        }
+    }
+
+    /// The rating of the player, [`RATING_INITIAL`] for a player without rated matches.
+    pub fn rating(&self, participant: &Participant) -> f64 {
+        match self.ratings.get(participant) {
+            Some(rating) => rating.rating,
+            None => RATING_INITIAL,
+        }
+    }
+
+    pub fn get(&self, participant: &Participant) -> Option<&Rating> {
+        self.ratings.get(participant)
+    }
+
+    /// All rated players, the best first.
+    pub fn leaderboard(&self) -> Vec<(&Participant, &Rating)> {
+        let mut leaderboard: Vec<_> = self.ratings.iter().collect();
+        leaderboard.sort_by(|(_, a), (_, b)| b.rating.total_cmp(&a.rating));
+        leaderboard
+    }
+
+    /// The computer player with the rating closest to the player, which makes the fairest match.
+    pub fn recommend_bot(&self, participant: &Participant) -> Difficulty {
+        let rating = self.rating(participant);
+        *Difficulty::ALL
+            .iter()
+            .min_by(|a, b| {
+                let a = (self.rating(&Participant::Bot(**a)) - rating).abs();
+                let b = (self.rating(&Participant::Bot(**b)) - rating).abs();
+                a.total_cmp(&b)
+            })
+            .expect("Unable to find a computer player!")
+    }
+}
+
+/// Loads the matches of a history file with one match per line.
+/// Broken lines are reported and skipped, so a single bad line doesn't lose the whole history.
+pub fn load_history(path: &Path) -> Vec<MatchResult> {
+    let file = match fs::read_to_string(path) {
+        Ok(file) => file,
+        Err(err) => {
+            if err.kind() != io::ErrorKind::NotFound {
+                eprintln!(
+                    "Unable to read the match history {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+            return Vec::new();
+        }
+    };
+
+    file.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| match serde_json::from_str(line) {
+            Ok(result) => Some(result),
+            Err(err) => {
+                eprintln!(
+                    "Skipping line {} of the match history {}: {}",
+                    index + 1,
+                    path.display(),
+                    err
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Adds a match to the end of a history file.
+pub fn append_history(path: &Path, result: &MatchResult) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(result)?)
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::arena::Arena;
+use crate::ball::Ball;
+use crate::game_state::countdown::CountdownState;
+use crate::game_state::play::{PlayState, Player, PlayerId};
+use crate::game_state::{GameState, Invalid};
+
+/// A recorded match, with one frame for every update of the game.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Replay {
+    /// The time between two frames in seconds.
+    pub frame_time: f64,
+    /// The arenas of the recorded matches, in the order they were played.
+    pub arenas: Vec<ReplayArena>,
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// The arena of the frames from the first frame on, until the arena of the next match.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayArena {
+    pub first_frame: usize,
+    pub arena: Arena,
+}
+
+/// Everything needed to render a single frame of a match.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ReplayFrame {
+    Countdown {
+        /// The remaining time of the countdown in seconds.
+        remaining: f64,
+    },
+    Play {
+        player_one: Player,
+        player_two: Player,
+        ball: Ball,
+    },
+}
+
+impl ReplayFrame {
+    /// Captures the current frame of the state, if it is a part of a match.
+    pub fn capture(state: &GameState) -> Option<Self> {
+        match state {
+            GameState::Countdown(countdown) => Some(ReplayFrame::Countdown {
+                remaining: countdown.remaining().as_secs_f64(),
+            }),
+            GameState::Play(play) => Some(ReplayFrame::from_play(play)),
+            _ => None,
+        }
+    }
+
+    /// Captures the players and the ball of a match.
+    pub fn from_play(play: &PlayState) -> Self {
+        ReplayFrame::Play {
+            player_one: play.player(&PlayerId::One).clone(),
+            player_two: play.player(&PlayerId::Two).clone(),
+            ball: play.ball().clone(),
+        }
+    }
+
+    /// Creates a state that renders exactly like the state this frame was captured from.
+    pub fn to_state(&self, arena: &Arena) -> GameState {
+        match self {
+            ReplayFrame::Countdown { remaining } => {
+                GameState::Countdown(Box::new(CountdownState::new(
+                    Duration::from_secs_f64(*remaining),
+                    GameState::Invalid(Box::new(Invalid)),
+                )))
+            }
+            ReplayFrame::Play {
+                player_one,
+                player_two,
+                ball,
+            } => GameState::Play(Box::new(PlayState::with_players(
+                player_one.clone(),
+                player_two.clone(),
+                ball.clone(),
+                arena.clone(),
+            ))),
+        }
+    }
+}
+
+/// The last frames of a rally, for the instant replay.
+/// Clones share the frames they have in common, so a snapshot of a match for rewinding
+/// doesn't copy the whole rally.
+#[derive(Clone, Default)]
+pub struct RallyFrames {
+    last: Option<Rc<RallyNode>>,
+    len: usize,
+}
+
+struct RallyNode {
+    frame: ReplayFrame,
+    previous: Option<Rc<RallyNode>>,
+}
+
+impl RallyFrames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a frame, only the last `keep` frames are needed afterwards.
+    pub fn push(&mut self, frame: ReplayFrame, keep: usize) {
+        self.last = Some(Rc::new(RallyNode {
+            frame,
+            previous: self.last.take(),
+        }));
+        self.len += 1;
+
+        // The shared frames can't be cut off, so the kept ones are copied once in a while
+        if self.len >= keep.max(1) * 2 {
+            let frames = self.frames(keep);
+            *self = Self::new();
+            for frame in frames {
+                self.push(frame, keep);
+            }
+        }
+    }
+
+    /// The last frames up to the count, the oldest first.
+    pub fn frames(&self, count: usize) -> Vec<ReplayFrame> {
+        let mut frames = Vec::new();
+        let mut node = self.last.as_deref();
+        while let Some(current) = node {
+            if frames.len() == count {
+                break;
+            }
+            frames.push(current.frame.clone());
+            node = current.previous.as_deref();
+        }
+        frames.reverse();
+        frames
+    }
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = serde_json::to_string(self)?;
+        fs::write(path, file)
+    }
+
+    /// Adds a frame of the state after it was updated, states outside of a match are skipped.
+    pub fn record(&mut self, state: &GameState, dt: f64) {
+        if let Some(frame) = ReplayFrame::capture(state) {
+            // Only a match in another arena needs to remember its arena
+            if let GameState::Play(play) = state {
+                if self.arena(self.frames.len()) != Some(play.arena()) {
+                    self.arenas.push(ReplayArena {
+                        first_frame: self.frames.len(),
+                        arena: play.arena().clone(),
+                    });
+                }
+            }
+
+            self.frame_time = dt;
+            self.frames.push(frame);
+        }
+    }
+
+    /// The arena the frame at the index was played in.
+    pub fn arena(&self, index: usize) -> Option<&Arena> {
+        self.arenas
+            .iter()
+            .rev()
+            .find(|arena| arena.first_frame <= index)
+            .map(|arena| &arena.arena)
+    }
+
+    /// Creates the state that renders the frame at the index.
+    pub fn state(&self, index: usize) -> GameState {
+        self.frames[index].to_state(self.arena(index).unwrap_or(&Arena::default()))
+    }
+}
@@ -0,0 +1,456 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::mem::swap;
+use std::path::Path;
+
+use graphics::draw_state::Blend;
+use graphics::glyph_cache::rusttype::GlyphCache;
+use graphics::types::Color;
+use graphics::{Context, DrawState, Graphics, ImageSize};
+use piston::RenderArgs;
+use rand::rngs::StdRng;
+use texture::{CreateTexture, Format, TextureOp, TextureSettings, UpdateTexture};
+
+use crate::audio::NullAudio;
+use crate::game_state::{GameImpl, GameOptions, GameState, GraphicsImpl, GraphicsOptions};
+use crate::{render_court, FONT};
+
+/// Game implementation details for rendering without a window.
+pub struct HeadlessGameImpl;
+impl GameImpl for HeadlessGameImpl {
+    type Rng = StdRng;
+    type GraphicsImpl = SoftwareGraphicsImpl;
+    type Audio = NullAudio;
+}
+
+pub struct SoftwareGraphicsImpl;
+impl GraphicsImpl for SoftwareGraphicsImpl {
+    type Graphics = SoftwareGraphics;
+    type CharacterCache = SoftwareGlyphCache;
+}
+
+/// The glyph cache of the software renderer, which stores the glyphs in textures in memory.
+pub type SoftwareGlyphCache = GlyphCache<'static, (), SoftwareTexture>;
+
+/// An RGBA texture in memory.
+#[derive(Clone, Debug)]
+pub struct SoftwareTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl SoftwareTexture {
+    /// The color at the texture coordinate, using the nearest pixel.
+    fn sample(&self, uv: [f64; 2]) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return [0.0; 4];
+        }
+
+        let x = ((uv[0] * self.width as f64) as i64).clamp(0, self.width as i64 - 1);
+        let y = ((uv[1] * self.height as f64) as i64).clamp(0, self.height as i64 - 1);
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &self.pixels[index..index + 4];
+        [
+            pixel[0] as f32 / 255.0,
+            pixel[1] as f32 / 255.0,
+            pixel[2] as f32 / 255.0,
+            pixel[3] as f32 / 255.0,
+        ]
+    }
+}
+
+impl ImageSize for SoftwareTexture {
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl TextureOp<()> for SoftwareTexture {
+    type Error = String;
+}
+
+impl CreateTexture<()> for SoftwareTexture {
+    fn create<S: Into<[u32; 2]>>(
+        _: &mut (),
+        _: Format,
+        memory: &[u8],
+        size: S,
+        _: &TextureSettings,
+    ) -> Result<Self, Self::Error> {
+        let [width, height] = size.into();
+        if memory.len() != (width * height * 4) as usize {
+            return Err(format!(
+                "Expected {} bytes for a {}x{} texture, got {}",
+                width * height * 4,
+                width,
+                height,
+                memory.len()
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels: memory.to_vec(),
+        })
+    }
+}
+
+impl UpdateTexture<()> for SoftwareTexture {
+    fn update<O, S>(
+        &mut self,
+        _: &mut (),
+        _: Format,
+        memory: &[u8],
+        offset: O,
+        size: S,
+    ) -> Result<(), Self::Error>
+    where
+        O: Into<[u32; 2]>,
+        S: Into<[u32; 2]>,
+    {
+        let [x, y] = offset.into();
+        let [width, height] = size.into();
+        if x + width > self.width || y + height > self.height {
+            return Err(format!(
+                "The area {}x{} at {},{} is outside of the {}x{} texture",
+                width, height, x, y, self.width, self.height
+            ));
+        }
+
+        let row_length = width as usize * 4;
+        for row in 0..height as usize {
+            let source = row * row_length;
+            let target = ((y as usize + row) * self.width as usize + x as usize) * 4;
+            self.pixels[target..target + row_length]
+                .copy_from_slice(&memory[source..source + row_length]);
+        }
+        Ok(())
+    }
+}
+
+/// Writes RGBA pixels, row by row from the top left corner, into a PNG file.
+pub fn save_png(path: &Path, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    Ok(())
+}
+
+/// A corner of a triangle in pixels, with the values that are interpolated across the triangle.
+#[derive(Clone, Copy)]
+struct Vertex {
+    position: [f64; 2],
+    uv: [f64; 2],
+    color: Color,
+}
+
+/// A framebuffer in memory that everything is rasterized into on the CPU.
+/// It doesn't need a window or a graphics card, so it also works on servers without a display.
+/// The stencil buffer is not supported.
+pub struct SoftwareGraphics {
+    width: u32,
+    height: u32,
+    /// The RGBA pixels, row by row from the top left corner.
+    pixels: Vec<u8>,
+}
+
+impl SoftwareGraphics {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The RGBA pixels, row by row from the top left corner.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        save_png(path, self.width, self.height, &self.pixels)
+    }
+
+    /// Converts a position from the -1 to 1 range of the graphics API into pixels.
+    fn to_pixels(&self, position: [f32; 2]) -> [f64; 2] {
+        [
+            (position[0] as f64 + 1.0) / 2.0 * self.width as f64,
+            (1.0 - position[1] as f64) / 2.0 * self.height as f64,
+        ]
+    }
+
+    /// Fills every pixel whose center is inside of the triangle.
+    fn fill_triangle(
+        &mut self,
+        draw_state: &DrawState,
+        vertices: [Vertex; 3],
+        texture: Option<&SoftwareTexture>,
+    ) {
+        let [a, mut b, mut c] = vertices;
+        let mut area = edge(a.position, b.position, c.position);
+        if area == 0.0 {
+            return;
+        }
+        // Always go around the triangle in the same direction
+        if area < 0.0 {
+            swap(&mut b, &mut c);
+            area = -area;
+        }
+
+        let [clip_x, clip_y, clip_width, clip_height] =
+            draw_state
+                .scissor
+                .unwrap_or([0, 0, self.width, self.height]);
+        let positions = [a.position, b.position, c.position];
+        let min = |axis: usize| positions.iter().map(|p| p[axis]).fold(f64::MAX, f64::min);
+        let max = |axis: usize| positions.iter().map(|p| p[axis]).fold(f64::MIN, f64::max);
+
+        let min_x = (min(0).floor().max(0.0) as u32).max(clip_x);
+        let min_y = (min(1).floor().max(0.0) as u32).max(clip_y);
+        let max_x = (max(0).ceil().max(0.0) as u32)
+            .min(clip_x + clip_width)
+            .min(self.width);
+        let max_y = (max(1).ceil().max(0.0) as u32)
+            .min(clip_y + clip_height)
+            .min(self.height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let center = [x as f64 + 0.5, y as f64 + 0.5];
+                let weight_a = edge(b.position, c.position, center);
+                let weight_b = edge(c.position, a.position, center);
+                let weight_c = edge(a.position, b.position, center);
+                if !(covers(weight_a, b.position, c.position)
+                    && covers(weight_b, c.position, a.position)
+                    && covers(weight_c, a.position, b.position))
+                {
+                    continue;
+                }
+
+                let weights = [weight_a / area, weight_b / area, weight_c / area];
+                let interpolate = |value: fn(&Vertex) -> f64| {
+                    value(&a) * weights[0] + value(&b) * weights[1] + value(&c) * weights[2]
+                };
+
+                let mut color = [0.0; 4];
+                for (channel, value) in color.iter_mut().enumerate() {
+                    *value = (a.color[channel] as f64 * weights[0]
+                        + b.color[channel] as f64 * weights[1]
+                        + c.color[channel] as f64 * weights[2]) as f32;
+                }
+                if let Some(texture) = texture {
+                    let texel = texture.sample([
+                        interpolate(|vertex| vertex.uv[0]),
+                        interpolate(|vertex| vertex.uv[1]),
+                    ]);
+                    for (value, texel) in color.iter_mut().zip(texel) {
+                        *value *= texel;
+                    }
+                }
+
+                self.blend(x, y, color, draw_state.blend);
+            }
+        }
+    }
+
+    /// Combines the color with the pixel in the framebuffer like the blend modes of OpenGL.
+    fn blend(&mut self, x: u32, y: u32, source: Color, blend: Option<Blend>) {
+        let index = ((y * self.width + x) * 4) as usize;
+        let pixel = &mut self.pixels[index..index + 4];
+        let target = [
+            pixel[0] as f32 / 255.0,
+            pixel[1] as f32 / 255.0,
+            pixel[2] as f32 / 255.0,
+            pixel[3] as f32 / 255.0,
+        ];
+
+        let mut result = [0.0; 4];
+        for channel in 0..3 {
+            let (s, t) = (source[channel], target[channel]);
+            result[channel] = match blend {
+                None => s,
+                Some(Blend::Alpha) => s * source[3] + t * (1.0 - source[3]),
+                Some(Blend::Add) => s + t,
+                Some(Blend::Lighter) => s * source[3] + t,
+                Some(Blend::Multiply) => s * t,
+                Some(Blend::Invert) => s * (1.0 - t) + t * (1.0 - s),
+            };
+        }
+        result[3] = match blend {
+            None => source[3],
+            Some(Blend::Alpha) => source[3] + target[3] * (1.0 - source[3]),
+            Some(Blend::Add) => source[3] + target[3],
+            Some(Blend::Multiply) => source[3] * target[3],
+            Some(Blend::Lighter | Blend::Invert) => target[3],
+        };
+
+        for (value, result) in pixel.iter_mut().zip(result) {
+            *value = (result.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Twice the signed area of the triangle between the edge from a to b and the point.
+/// Swapping a and b exactly negates the result, so neighbouring triangles agree on their shared edge.
+fn edge(a: [f64; 2], b: [f64; 2], point: [f64; 2]) -> f64 {
+    if (a[1], a[0]) > (b[1], b[0]) {
+        return -edge(b, a, point);
+    }
+    (b[0] - a[0]) * (point[1] - a[1]) - (b[1] - a[1]) * (point[0] - a[0])
+}
+
+/// Whether a pixel is on the inner side of the edge from a to b.
+/// Pixels exactly on the edge only belong to one of the two triangles sharing it,
+/// otherwise transparent shapes would be blended twice along the diagonals.
+fn covers(weight: f64, a: [f64; 2], b: [f64; 2]) -> bool {
+    weight > 0.0 || (weight == 0.0 && (b[1] > a[1] || (b[1] == a[1] && b[0] < a[0])))
+}
+
+impl Graphics for SoftwareGraphics {
+    type Texture = SoftwareTexture;
+
+    fn clear_color(&mut self, color: Color) {
+        let pixel = color.map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        for chunk in self.pixels.chunks_exact_mut(4) {
+            chunk.copy_from_slice(&pixel);
+        }
+    }
+
+    fn clear_stencil(&mut self, _: u8) {}
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        f(&mut |positions| {
+            for triangle in positions.chunks_exact(3) {
+                let vertices = [0, 1, 2].map(|corner| Vertex {
+                    position: self.to_pixels(triangle[corner]),
+                    uv: [0.0; 2],
+                    color: *color,
+                });
+                self.fill_triangle(draw_state, vertices, None);
+            }
+        });
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        f(&mut |positions, colors| {
+            for (triangle, colors) in positions.chunks_exact(3).zip(colors.chunks_exact(3)) {
+                let vertices = [0, 1, 2].map(|corner| Vertex {
+                    position: self.to_pixels(triangle[corner]),
+                    uv: [0.0; 2],
+                    color: colors[corner],
+                });
+                self.fill_triangle(draw_state, vertices, None);
+            }
+        });
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &SoftwareTexture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        f(&mut |positions, uvs| {
+            for (triangle, uvs) in positions.chunks_exact(3).zip(uvs.chunks_exact(3)) {
+                let vertices = [0, 1, 2].map(|corner| Vertex {
+                    position: self.to_pixels(triangle[corner]),
+                    uv: uvs[corner].map(|value| value as f64),
+                    color: *color,
+                });
+                self.fill_triangle(draw_state, vertices, Some(texture));
+            }
+        });
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &SoftwareTexture, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        f(&mut |positions, uvs, colors| {
+            for ((triangle, uvs), colors) in positions
+                .chunks_exact(3)
+                .zip(uvs.chunks_exact(3))
+                .zip(colors.chunks_exact(3))
+            {
+                let vertices = [0, 1, 2].map(|corner| Vertex {
+                    position: self.to_pixels(triangle[corner]),
+                    uv: uvs[corner].map(|value| value as f64),
+                    color: colors[corner],
+                });
+                self.fill_triangle(draw_state, vertices, Some(texture));
+            }
+        });
+    }
+}
+
+/// Renders game states into a framebuffer in memory instead of a window.
+pub struct SoftwareRenderer {
+    pub graphics: SoftwareGraphics,
+    pub character_cache: SoftwareGlyphCache,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            graphics: SoftwareGraphics::new(width, height),
+            character_cache: GlyphCache::from_bytes(FONT, (), TextureSettings::new())
+                .expect("Unable to create font!"),
+        }
+    }
+
+    /// Renders a frame of the state exactly like the window would with the same size.
+    pub fn render<Impl: GameImpl<GraphicsImpl = SoftwareGraphicsImpl>>(
+        &mut self,
+        state: &mut GameState,
+        options: &mut GameOptions<Impl>,
+    ) {
+        let size = [self.graphics.width, self.graphics.height];
+        let args = RenderArgs {
+            ext_dt: 0.0,
+            window_size: [size[0] as f64, size[1] as f64],
+            draw_size: size,
+        };
+
+        let mut context = Context::new_viewport(args.viewport());
+        let mut graphic_options = GraphicsOptions {
+            graphics: &mut self.graphics,
+            character_cache: &mut self.character_cache,
+            ctx: &mut context,
+        };
+        render_court(state, &mut graphic_options, &args, options);
+    }
+}
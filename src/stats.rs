@@ -0,0 +1,116 @@
+use crate::event::{EventSubscriber, GameEvent};
+use crate::game_state::play::PlayerId;
+
+/// The statistics of a single player in a match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerStats {
+    pub hits: u32,
+    pub points: u32,
+    /// The fastest the ball was after a hit of the player.
+    pub fastest_ball: f64,
+    /// The time in seconds the player was the last one that hit the ball.
+    pub possession: f64,
+}
+
+/// The statistics of a match, counted from the events of the match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchStats {
+    players: [PlayerStats; 2],
+    /// The durations of the finished rallies in seconds.
+    rallies: Vec<f64>,
+    /// The most hits in a single rally.
+    longest_rally: u32,
+    /// The hits of the current rally.
+    rally_hits: u32,
+    /// The time of the current rally in seconds.
+    rally_time: f64,
+    /// The player that hit the ball last in the current rally.
+    possession: Option<PlayerId>,
+}
+
+impl MatchStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn player(&self, player: &PlayerId) -> &PlayerStats {
+        &self.players[player.index()]
+    }
+
+    /// The most hits in a single rally.
+    pub fn longest_rally(&self) -> u32 {
+        self.longest_rally
+    }
+
+    /// The average duration of the finished rallies in seconds, None before the first point.
+    pub fn average_rally(&self) -> Option<f64> {
+        if self.rallies.is_empty() {
+            return None;
+        }
+        Some(self.rallies.iter().sum::<f64>() / self.rallies.len() as f64)
+    }
+
+    /// Counts the time of the rally, should be called for every update while the ball is in play.
+    pub fn advance(&mut self, dt: f64) {
+        self.rally_time += dt;
+        if let Some(player) = self.possession {
+            self.players[player.index()].possession += dt;
+        }
+    }
+
+    /// The statistics of both players as rows of a table, with the name and the values of player one and two.
+    pub fn rows(&self) -> Vec<(&'static str, String, String)> {
+        let [one, two] = &self.players;
+        vec![
+            ("Points", one.points.to_string(), two.points.to_string()),
+            ("Hits", one.hits.to_string(), two.hits.to_string()),
+            (
+                "Fastest ball",
+                format!("{:.0}", one.fastest_ball),
+                format!("{:.0}", two.fastest_ball),
+            ),
+            (
+                "Possession",
+                format!("{:.1} s", one.possession),
+                format!("{:.1} s", two.possession),
+            ),
+        ]
+    }
+
+    /// The statistics of the rallies in a single line.
+    pub fn rally_summary(&self) -> String {
+        let average = match self.average_rally() {
+            Some(average) => format!("{:.1} s", average),
+            None => "-".to_string(),
+        };
+        format!(
+            "Longest rally: {} hits   Average rally: {}",
+            self.longest_rally, average
+        )
+    }
+}
+
+impl EventSubscriber for MatchStats {
+    fn handle(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::PaddleHit { player, speed, .. } => {
+                let stats = &mut self.players[player.index()];
+                stats.hits += 1;
+                stats.fastest_ball = stats.fastest_ball.max(*speed);
+
+                self.rally_hits += 1;
+                self.longest_rally = self.longest_rally.max(self.rally_hits);
+                self.possession = Some(*player);
+            }
+            GameEvent::Scored { player, .. } => {
+                self.players[player.index()].points += 1;
+
+                self.rallies.push(self.rally_time);
+                self.rally_hits = 0;
+                self.rally_time = 0.0;
+                self.possession = None;
+            }
+            _ => {}
+        }
+    }
+}
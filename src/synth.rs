@@ -0,0 +1,134 @@
+use crate::AUDIO_SAMPLE_RATE;
+
+/// The shape of a single period of a tone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    /// A new random value of -1 or 1 for every period, like the noise channel of old consoles.
+    Noise,
+}
+
+/// How the loudness of a tone changes over time.
+/// The tone rises to the full loudness in the attack, falls to the sustain level in the decay,
+/// stays there while the tone is held and fades out in the release.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Envelope {
+    /// The time in seconds to reach the full loudness.
+    pub attack: f64,
+    /// The time in seconds to fall to the sustain level.
+    pub decay: f64,
+    /// The loudness from 0 to 1 while the tone is held.
+    pub sustain: f64,
+    /// The time in seconds to fade out after the tone was released.
+    pub release: f64,
+}
+
+impl Envelope {
+    /// The loudness from 0 to 1 at the time of a tone that is held for the duration.
+    pub fn level(&self, time: f64, duration: f64) -> f64 {
+        if time < duration {
+            return self.held_level(time);
+        }
+
+        let released = time - duration;
+        if released >= self.release {
+            0.0
+        } else {
+            self.held_level(duration) * (1.0 - released / self.release)
+        }
+    }
+
+    /// The loudness before the tone is released.
+    fn held_level(&self, time: f64) -> f64 {
+        if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (time - self.attack) / self.decay
+        } else {
+            self.sustain
+        }
+    }
+}
+
+/// A single bleep of the synthesizer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tone {
+    pub waveform: Waveform,
+    /// The frequency at the start in hertz.
+    pub frequency: f64,
+    /// The frequency at the end in hertz, the pitch slides evenly from the start to the end.
+    pub end_frequency: f64,
+    /// The time in seconds the tone is held before it is released.
+    pub duration: f64,
+    pub envelope: Envelope,
+    /// The loudness from 0 to 1.
+    pub volume: f32,
+}
+
+impl Tone {
+    /// The time in seconds until the tone has faded out.
+    pub fn length(&self) -> f64 {
+        self.duration + self.envelope.release
+    }
+
+    /// The samples of the tone at [`AUDIO_SAMPLE_RATE`].
+    pub fn render(&self) -> Vec<f32> {
+        let rate = AUDIO_SAMPLE_RATE as f64;
+        let length = (self.length() * rate).round() as usize;
+
+        let mut noise = Lfsr::new();
+        let mut phase: f64 = 0.0;
+        (0..length)
+            .map(|index| {
+                let time = index as f64 / rate;
+                let value = match self.waveform {
+                    Waveform::Square if phase < 0.5 => 1.0,
+                    Waveform::Square => -1.0,
+                    Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                    Waveform::Noise => noise.value(),
+                };
+
+                let progress = (time / self.length()).min(1.0);
+                let frequency = self.frequency + (self.end_frequency - self.frequency) * progress;
+                phase += frequency / rate;
+                if phase >= 1.0 {
+                    phase = phase.fract();
+                    noise.step();
+                }
+
+                value as f32 * self.envelope.level(time, self.duration) as f32 * self.volume
+            })
+            .collect()
+    }
+}
+
+/// Renders the tones one after another.
+pub fn render_sequence(tones: &[Tone]) -> Vec<f32> {
+    tones.iter().flat_map(|tone| tone.render()).collect()
+}
+
+/// A linear feedback shift register, which gives the same random bits every time.
+struct Lfsr {
+    state: u16,
+}
+
+impl Lfsr {
+    fn new() -> Self {
+        Self { state: 1 }
+    }
+
+    fn value(&self) -> f64 {
+        if self.state & 1 == 1 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    /// Shifts in the next bit, with the taps of the 15 bit noise of the NES.
+    fn step(&mut self) {
+        let bit = (self.state ^ (self.state >> 1)) & 1;
+        self.state = (self.state >> 1) | (bit << 14);
+    }
+}
@@ -0,0 +1,209 @@
+use std::io::{self, Write};
+
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::{self, Print, SetBackgroundColor, SetForegroundColor};
+use graphics::types::Color;
+
+use crate::arena::Rect;
+use crate::game_state::play::{PlayState, PlayerId};
+use crate::game_state::GameState;
+use crate::viewport::CourtViewport;
+use crate::{
+    BACKGROUND_COLOR, BALL_COLOR, BALL_SIZE, COURT_SIZE, GOAL_COLOR, INSTANT_REPLAY_COLOR,
+    LETTERBOX_COLOR, OBSTACLE_COLOR, PADDLE_COLOR, PADDLE_SIZE, SCORE_COLOR, SCORE_COUNTDOWN_COLOR,
+    SCORE_Y_GAP,
+};
+
+/// The character for a cell showing two pixels, the top one in the foreground color
+/// and the bottom one in the background color.
+const HALF_BLOCK: char = '▀';
+
+/// A grid of terminal cells that the court is drawn into.
+/// Every cell shows two pixels on top of each other with a half block character,
+/// which makes the pixels roughly square in most terminal fonts.
+pub struct TerminalCanvas {
+    columns: u16,
+    rows: u16,
+    /// Two pixels for every cell, row by row from the top left corner.
+    pixels: Vec<Color>,
+    /// Text drawn over the pixels with its color, for every cell.
+    text: Vec<Option<(char, Color)>>,
+    viewport: CourtViewport,
+}
+
+impl TerminalCanvas {
+    pub fn new(columns: u16, rows: u16) -> Self {
+        let cells = columns as usize * rows as usize;
+        Self {
+            columns,
+            rows,
+            pixels: vec![LETTERBOX_COLOR; cells * 2],
+            text: vec![None; cells],
+            viewport: CourtViewport::new([columns as f64, rows as f64 * 2.0]),
+        }
+    }
+
+    /// The color of a pixel, there are two pixels in every row of cells.
+    pub fn pixel(&self, x: u16, y: u16) -> Color {
+        self.pixels[y as usize * self.columns as usize + x as usize]
+    }
+
+    /// The character of the text in a cell, if there is any.
+    pub fn text(&self, column: u16, row: u16) -> Option<char> {
+        self.text[row as usize * self.columns as usize + column as usize].map(|(ch, _)| ch)
+    }
+
+    /// Draws the state into the canvas.
+    /// Only a match with its countdown and replays is shown, other states leave the court empty.
+    pub fn render(&mut self, state: &GameState) {
+        self.fill_rect(
+            &Rect::new(0.0, 0.0, COURT_SIZE.0 as f64, COURT_SIZE.1 as f64),
+            BACKGROUND_COLOR,
+        );
+
+        match state {
+            GameState::Play(play) => self.render_play(play),
+            GameState::InstantReplay(replay) => {
+                if let Some(GameState::Play(play)) = replay.state() {
+                    self.render_play(&play);
+                }
+                let position = [(COURT_SIZE.0 as f64) / 2.0, (COURT_SIZE.1 as f64) / 4.0];
+                self.print(position, "REPLAY", INSTANT_REPLAY_COLOR);
+            }
+            GameState::Countdown(countdown) => {
+                let secs = format!("{}", countdown.remaining().as_secs());
+                let center = [(COURT_SIZE.0 as f64) / 2.0, (COURT_SIZE.1 as f64) / 2.0];
+                self.print(center, &secs, SCORE_COUNTDOWN_COLOR);
+            }
+            _ => {}
+        }
+    }
+
+    fn render_play(&mut self, play: &PlayState) {
+        // The game uses a y axis pointing up, flip everything into the top left corner origin
+        let flip = |x: f64, y: f64, width: f64, height: f64| {
+            Rect::new(x, (COURT_SIZE.1 as f64) - y - height, width, height)
+        };
+
+        for goal in &play.arena().goals {
+            let rect = &goal.rect;
+            self.fill_rect(&flip(rect.x, rect.y, rect.width, rect.height), GOAL_COLOR);
+        }
+        for rect in &play.arena().obstacles {
+            self.fill_rect(
+                &flip(rect.x, rect.y, rect.width, rect.height),
+                OBSTACLE_COLOR,
+            );
+        }
+
+        let (paddle_width, paddle_height) = (PADDLE_SIZE.0 as f64, PADDLE_SIZE.1 as f64);
+        for player in [play.player(&PlayerId::One), play.player(&PlayerId::Two)] {
+            let paddle = &player.paddle;
+            let rect = flip(paddle.x(), paddle.y(), paddle_width, paddle_height);
+            self.fill_rect(&rect, PADDLE_COLOR);
+        }
+
+        // The ball is too small to show its rotation
+        let (ball_width, ball_height) = (BALL_SIZE.0 as f64, BALL_SIZE.1 as f64);
+        let ball = play.ball();
+        let rect = flip(ball.x, ball.y, ball_width, ball_height);
+        self.fill_rect(&rect, BALL_COLOR);
+
+        let score_one = format!("{}", play.player(&PlayerId::One).score);
+        let score_two = format!("{}", play.player(&PlayerId::Two).score);
+        self.print(
+            [(COURT_SIZE.0 as f64) / 4.0, SCORE_Y_GAP],
+            &score_one,
+            SCORE_COLOR,
+        );
+        self.print(
+            [(COURT_SIZE.0 as f64) / 4.0 * 3.0, SCORE_Y_GAP],
+            &score_two,
+            SCORE_COLOR,
+        );
+    }
+
+    /// Blends a rectangle in court coordinates into the pixels.
+    /// Everything covers at least one pixel, so small objects like the ball never disappear.
+    fn fill_rect(&mut self, rect: &Rect, color: Color) {
+        let [left, top] = self.to_pixels([rect.x, rect.y]);
+        let [right, bottom] = self.to_pixels([rect.x + rect.width, rect.y + rect.height]);
+        let height = self.rows as f64 * 2.0;
+
+        let (left, top) = (left.round().max(0.0), top.round().max(0.0));
+        let right = right.round().max(left + 1.0).min(self.columns as f64);
+        let bottom = bottom.round().max(top + 1.0).min(height);
+
+        for y in top as usize..bottom as usize {
+            for x in left as usize..right as usize {
+                let pixel = &mut self.pixels[y * self.columns as usize + x];
+                for channel in 0..3 {
+                    pixel[channel] = color[channel] * color[3] + pixel[channel] * (1.0 - color[3]);
+                }
+            }
+        }
+    }
+
+    /// Writes text starting at the position in court coordinates.
+    /// Text that doesn't fit into the canvas is cut off.
+    fn print(&mut self, position: [f64; 2], text: &str, color: Color) {
+        let [x, y] = self.to_pixels(position);
+        let row = (y / 2.0) as usize;
+        if row >= self.rows as usize {
+            return;
+        }
+
+        for (index, ch) in text.chars().enumerate() {
+            let column = x as usize + index;
+            if column < self.columns as usize {
+                self.text[row * self.columns as usize + column] = Some((ch, color));
+            }
+        }
+    }
+
+    fn to_pixels(&self, position: [f64; 2]) -> [f64; 2] {
+        let scale = self.viewport.scale;
+        [
+            self.viewport.offset[0] + position[0] * scale,
+            self.viewport.offset[1] + position[1] * scale,
+        ]
+    }
+
+    /// Writes the canvas to the terminal, starting in the top left corner.
+    pub fn draw<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut colors = None;
+        for row in 0..self.rows {
+            queue!(out, MoveTo(0, row))?;
+
+            for column in 0..self.columns {
+                let top = self.pixel(column, row * 2);
+                let bottom = self.pixel(column, row * 2 + 1);
+                let index = row as usize * self.columns as usize + column as usize;
+                let (ch, foreground, background) = match self.text[index] {
+                    Some((ch, color)) => (ch, color, top),
+                    None => (HALF_BLOCK, top, bottom),
+                };
+
+                // Only change the colors if they are different from the last cell
+                let cell_colors = (to_terminal_color(foreground), to_terminal_color(background));
+                if colors != Some(cell_colors) {
+                    queue!(
+                        out,
+                        SetForegroundColor(cell_colors.0),
+                        SetBackgroundColor(cell_colors.1)
+                    )?;
+                    colors = Some(cell_colors);
+                }
+                queue!(out, Print(ch))?;
+            }
+        }
+        queue!(out, style::ResetColor)?;
+        out.flush()
+    }
+}
+
+fn to_terminal_color(color: Color) -> style::Color {
+    let [r, g, b, _] = color.map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8);
+    style::Color::Rgb { r, g, b }
+}
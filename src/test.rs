@@ -1,4 +1,44 @@
-use crate::game_state::play::PlayState;
+use crate::achievement::Achievements;
+use crate::ai::{predict_intercept, Difficulty};
+use crate::arena::{Arena, Goal, Rect, Spawn, SpawnKind};
+use crate::audio::{save_wav, NullAudio, Sound, WavRecorder};
+use crate::ball::Ball;
+use crate::bench::{load_controllers, play_match, wilson_interval, BenchSettings, Crosstable};
+use crate::config::{BallConfig, Config, DisplayConfig, DisplayMode, PaddleConfig, SpeedCurve};
+use crate::debug::{DebugOverlay, RewindBuffer, TimeControl};
+use crate::event::{EventQueue, EventSubscriber, GameEvent};
+use crate::game_state::bracket::BracketState;
+use crate::game_state::breakout::BreakoutState;
+use crate::game_state::countdown::CountdownState;
+use crate::game_state::editor::EditorState;
+use crate::game_state::play::{PlayState, Player, PlayerId};
+use crate::game_state::settings::SettingsState;
+use crate::game_state::{GameImpl, GameOptions, GameState, GameStateTrait};
+use crate::gamepad::{stick_input, Gamepads, MockGamepads};
+use crate::paddle::{Paddle, PaddleInput};
+use crate::profile::{MatchResult, Participant, Profile, ProfileStore};
+use crate::rating::{expected_score, Ratings};
+use crate::replay::{RallyFrames, Replay, ReplayFrame};
+use crate::software::{save_png, HeadlessGameImpl, SoftwareGraphicsImpl, SoftwareRenderer};
+use crate::stats::MatchStats;
+use crate::synth::{Envelope, Tone, Waveform};
+use crate::terminal::TerminalCanvas;
+use crate::tournament::{Format, Stage, Tournament};
+use crate::viewport::CourtViewport;
+use crate::{
+    ACHIEVEMENT_TOAST_TIME, BACKGROUND_COLOR, BUTTON_DEBUG_FASTER, BUTTON_DEBUG_OVERLAY,
+    BUTTON_DEBUG_PAUSE, BUTTON_DEBUG_REWIND, BUTTON_EDITOR_DELETE, BUTTON_EDITOR_GOAL,
+    BUTTON_EDITOR_GRID, BUTTON_EDITOR_SAVE, BUTTON_EDITOR_SPAWN, BUTTON_EDITOR_TEST_PLAY,
+    BUTTON_RETURN_TO_EDITOR, COURT_SIZE, LETTERBOX_COLOR, PADDLE_COLOR, START_BALL_VELOCITY,
+    WINDOW_SIZE,
+};
+use piston::{Button, Key, MouseButton, UpdateArgs};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[test]
 fn test_box_box_collision() {
@@ -12,3 +52,2149 @@ fn test_box_box_collision() {
         false
     );
 }
+
+#[test]
+fn test_ball_bounces_off_box() {
+    let obstacle = Rect::new(100.0, 0.0, 50.0, 500.0);
+
+    // A ball moving right into the left side of the box
+    let mut ball = Ball::new(95.0, 250.0, 200.0, 10.0);
+    assert!(ball.bounce_off_box(&obstacle));
+    assert!(ball.x_velocity < 0.0);
+    assert_eq!(ball.y_velocity, 10.0);
+    assert!(!obstacle.is_colliding_with_box(ball.x + 0.1, ball.y, 9.0, 10.0));
+
+    // A ball far away from the box
+    assert!(!ball.bounce_off_box(&Rect::new(500.0, 0.0, 10.0, 10.0)));
+}
+
+/// Drags the mouse with the button held down between two points of the court.
+/// The points have the origin in the bottom left corner, like the arena.
+fn drag(
+    state: &mut GameState,
+    options: &GameOptions<HeadlessGameImpl>,
+    button: MouseButton,
+    from: [f64; 2],
+    to: [f64; 2],
+) {
+    let window = |point: [f64; 2]| [point[0], (COURT_SIZE.1 as f64) - point[1]];
+    state.mouse_move(&window(from), options);
+    state.button_press(&Button::Mouse(button), options);
+    state.mouse_move(&window(to), options);
+    state.button_release(&Button::Mouse(button), options);
+}
+
+fn editor_arena(state: &GameState) -> &Arena {
+    let GameState::Editor(editor) = state else {
+        panic!("Unable to find the editor!");
+    };
+    editor.arena()
+}
+
+#[test]
+fn test_editor() {
+    let dir = test_dir("editor");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("arena.json");
+    let mut options = golden_options();
+    let mut state = GameState::Editor(Box::new(EditorState::new(Arena::default(), path.clone())));
+    let left = MouseButton::Left;
+
+    // A new obstacle snaps to the grid
+    drag(&mut state, &options, left, [102.0, 103.0], [198.0, 247.0]);
+    assert_eq!(
+        editor_arena(&state).obstacles,
+        [Rect::new(100.0, 100.0, 100.0, 150.0)]
+    );
+
+    // Dragging it moves it, dragging its top right corner resizes it
+    drag(&mut state, &options, left, [150.0, 150.0], [260.0, 210.0]);
+    assert_eq!(
+        editor_arena(&state).obstacles,
+        [Rect::new(200.0, 150.0, 100.0, 150.0)]
+    );
+    drag(&mut state, &options, left, [300.0, 300.0], [352.0, 374.0]);
+    assert_eq!(
+        editor_arena(&state).obstacles,
+        [Rect::new(200.0, 150.0, 150.0, 225.0)]
+    );
+
+    // Without the grid the obstacle is placed where the mouse is, the delete key removes it again
+    press(&mut state, &options, &[BUTTON_EDITOR_GRID]);
+    drag(&mut state, &options, left, [12.0, 13.0], [37.0, 41.0]);
+    assert_eq!(
+        editor_arena(&state).obstacles[1],
+        Rect::new(12.0, 13.0, 25.0, 28.0)
+    );
+    press(
+        &mut state,
+        &options,
+        &[BUTTON_EDITOR_DELETE, BUTTON_EDITOR_GRID],
+    );
+    assert_eq!(editor_arena(&state).obstacles.len(), 1);
+
+    // The spawn of player one and a goal, which is deleted with a right click
+    press(
+        &mut state,
+        &options,
+        &[BUTTON_EDITOR_SPAWN, BUTTON_EDITOR_SPAWN],
+    );
+    drag(&mut state, &options, left, [48.0, 252.0], [48.0, 252.0]);
+    press(&mut state, &options, &[BUTTON_EDITOR_GOAL]);
+    drag(&mut state, &options, left, [900.0, 0.0], [1000.0, 500.0]);
+    assert_eq!(
+        editor_arena(&state).goals,
+        [Goal {
+            rect: Rect::new(900.0, 0.0, 100.0, 500.0),
+            scorer: PlayerId::One,
+        }]
+    );
+    drag(
+        &mut state,
+        &options,
+        MouseButton::Right,
+        [950.0, 50.0],
+        [950.0, 50.0],
+    );
+    assert!(editor_arena(&state).goals.is_empty());
+    assert_eq!(
+        editor_arena(&state).spawns,
+        [Spawn {
+            kind: SpawnKind::PlayerOne,
+            x: 50.0,
+            y: 250.0,
+        }]
+    );
+
+    // The saved file loads the same arena
+    press(&mut state, &options, &[BUTTON_EDITOR_SAVE]);
+    let arena = editor_arena(&state).clone();
+    assert_eq!(Arena::load(&path).unwrap(), arena);
+
+    // Test playing starts a match in the arena after the countdown and returns to the editor
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    press(&mut state, &options, &[BUTTON_EDITOR_TEST_PLAY]);
+    while !matches!(state, GameState::Play(_)) {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+    }
+    let GameState::Play(play) = &state else {
+        unreachable!()
+    };
+    assert_eq!(*play.arena(), arena);
+    press(&mut state, &options, &[BUTTON_RETURN_TO_EDITOR]);
+    state = state
+        .update(&args, &mut options)
+        .unwrap_or_else(|state| state);
+    assert_eq!(*editor_arena(&state), arena);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_paddle_acceleration() {
+    let config = PaddleConfig::default();
+    let args = UpdateArgs { dt: 0.01 };
+    let mut paddle = Paddle::new(0.0, 0.0);
+
+    // The paddle speeds up until it reaches the maximum speed
+    paddle.input_mut().press_up();
+    paddle.update(&args, 0.0..10_000.0, &config);
+    assert!(paddle.velocity() > 0.0 && paddle.velocity() < config.max_speed);
+    for _ in 0..100 {
+        paddle.update(&args, 0.0..10_000.0, &config);
+    }
+    assert_eq!(paddle.velocity(), config.max_speed);
+
+    // Friction stops the paddle without moving it in the other direction
+    paddle.input_mut().release_up();
+    for _ in 0..100 {
+        paddle.update(&args, 0.0..10_000.0, &config);
+        assert!(paddle.velocity() >= 0.0);
+    }
+    assert_eq!(paddle.velocity(), 0.0);
+}
+
+#[test]
+fn test_analog_paddle() {
+    let config = PaddleConfig::default();
+    let args = UpdateArgs { dt: 0.01 };
+    let mut paddle = Paddle::new(0.0, 5_000.0);
+
+    // The paddle speeds up to the share of the max speed the stick is pushed
+    *paddle.input_mut() = PaddleInput::Analog(0.5);
+    for _ in 0..100 {
+        paddle.update(&args, 0.0..10_000.0, &config);
+        assert!(paddle.velocity() <= config.max_speed / 2.0);
+    }
+    assert_eq!(paddle.velocity(), config.max_speed / 2.0);
+
+    // Pulling the stick back slows it down with the acceleration, not the friction
+    *paddle.input_mut() = PaddleInput::Analog(-1.0);
+    paddle.update(&args, 0.0..10_000.0, &config);
+    let slowed = config.max_speed / 2.0 - config.acceleration * args.dt;
+    assert!((paddle.velocity() - slowed).abs() < 1e-9);
+    for _ in 0..100 {
+        paddle.update(&args, 0.0..10_000.0, &config);
+    }
+    assert_eq!(paddle.velocity(), -config.max_speed);
+
+    // The stick is clamped to the max speed
+    *paddle.input_mut() = PaddleInput::Analog(-3.0);
+    paddle.update(&args, 0.0..10_000.0, &config);
+    assert_eq!(paddle.velocity(), -config.max_speed);
+}
+
+#[test]
+fn test_paddle_momentum_transfer() {
+    let args = UpdateArgs { dt: 0.01 };
+    // Hits the ball with a paddle moving at the velocity and returns the y velocity of the ball
+    let hit = |velocity: f64| -> f64 {
+        let mut options = golden_options();
+        let mut paddle = Paddle::new(50.0, 5_000.0);
+        *paddle.input_mut() = PaddleInput::Analog(velocity / options.config.paddle.max_speed);
+        for _ in 0..100 {
+            paddle.update(&args, 0.0..10_000.0, &options.config.paddle);
+        }
+        assert_eq!(paddle.velocity(), velocity);
+
+        let mut ball = Ball::new(paddle.x() + 5.0, paddle.y() + 20.0, -200.0, 0.0);
+        PlayState::check_ball_paddle_collide(&PlayerId::One, &paddle, &mut ball, &mut options);
+        assert!(ball.x_velocity > 0.0);
+        assert!(ball.speed() < options.config.ball.max_speed);
+        ball.y_velocity
+    };
+
+    // The same hit with a moving paddle slices the ball into the direction the paddle moves
+    let transfer = PaddleConfig::default().momentum_transfer;
+    let still = hit(0.0);
+    let up = hit(200.0);
+    let down = hit(-200.0);
+    assert!((up - still - 200.0 * transfer).abs() < 1e-9);
+    assert!((down - still + 200.0 * transfer).abs() < 1e-9);
+}
+
+#[test]
+fn test_gamepads() {
+    assert_eq!(stick_input(0.0), PaddleInput::None);
+    assert_eq!(stick_input(-0.1), PaddleInput::None);
+    assert_eq!(stick_input(1.0), PaddleInput::Analog(1.0));
+    assert_eq!(stick_input(-2.0), PaddleInput::Analog(-1.0));
+    let PaddleInput::Analog(half) = stick_input(0.575) else {
+        panic!("Unable to find an analog input outside of the dead zone!");
+    };
+    assert!((half - 0.5).abs() < 1e-9);
+
+    // The first two gamepads control player one and two, the third one waits
+    let mut gamepads = Gamepads::new(MockGamepads::new());
+    gamepads.backend_mut().connect(7);
+    gamepads.backend_mut().connect(3);
+    gamepads.backend_mut().connect(9);
+    assert!(gamepads.poll().is_empty());
+    assert_eq!(gamepads.player(7), Some(PlayerId::One));
+    assert_eq!(gamepads.player(3), Some(PlayerId::Two));
+    assert_eq!(gamepads.player(9), None);
+
+    // Only changes of the inputs are reported, the waiting gamepad is ignored
+    gamepads.backend_mut().move_stick(3, 1.0);
+    gamepads.backend_mut().move_stick(3, 1.0);
+    gamepads.backend_mut().move_stick(9, -1.0);
+    assert_eq!(gamepads.poll(), [(PlayerId::Two, PaddleInput::Analog(1.0))]);
+
+    // A disconnected gamepad stops its paddle and the waiting one takes over
+    gamepads.backend_mut().move_stick(7, -1.0);
+    gamepads.backend_mut().disconnect(7);
+    assert_eq!(
+        gamepads.poll(),
+        [
+            (PlayerId::One, PaddleInput::Analog(-1.0)),
+            (PlayerId::One, PaddleInput::None)
+        ]
+    );
+    assert_eq!(gamepads.gamepad(&PlayerId::One), Some(9));
+
+    // Switching sides swaps the gamepads of the players and stops both paddles
+    gamepads.backend_mut().switch_sides(9);
+    assert_eq!(gamepads.poll(), [(PlayerId::Two, PaddleInput::None)]);
+    assert_eq!(gamepads.gamepad(&PlayerId::One), Some(3));
+    assert_eq!(gamepads.gamepad(&PlayerId::Two), Some(9));
+
+    // The inputs move the paddles of the match
+    let mut options = golden_options();
+    let mut state = GameState::Play(Box::new(PlayState::new(&mut options)));
+    gamepads.backend_mut().move_stick(9, 1.0);
+    for (player, input) in gamepads.poll() {
+        state.set_paddle_input(&player, input);
+    }
+    let args = UpdateArgs { dt: 0.1 };
+    let Ok(GameState::Play(play)) = state.update(&args, &mut options) else {
+        panic!("Unable to find the match after an update!");
+    };
+    assert_eq!(play.player(&PlayerId::One).paddle.velocity(), 0.0);
+    assert!(play.player(&PlayerId::Two).paddle.velocity() > 0.0);
+
+    // A stick pushed during the replay and the countdown moves the paddle once the rally starts
+    let mut state = score_point(&mut options);
+    assert!(matches!(state, GameState::InstantReplay(_)));
+    gamepads.backend_mut().move_stick(3, -1.0);
+    for (player, input) in gamepads.poll() {
+        state.set_paddle_input(&player, input);
+    }
+    while !matches!(state, GameState::Play(_)) {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+    }
+    let Ok(GameState::Play(play)) = state.update(&args, &mut options) else {
+        panic!("Unable to find the match after an update!");
+    };
+    assert!(play.player(&PlayerId::One).paddle.velocity() < 0.0);
+}
+
+#[test]
+fn test_ball_spin() {
+    let config = BallConfig::default();
+    let args = UpdateArgs { dt: 0.01 };
+
+    // A ball spinning counterclockwise while moving right curves upwards
+    let mut ball = Ball::new(100.0, 250.0, 200.0, 0.0);
+    ball.spin = 5.0;
+    ball.update(&args, 0.0..500.0, &config);
+    assert!(ball.y_velocity > 0.0);
+    assert!(ball.spin < 5.0);
+
+    // Bouncing off the bottom wall pushes the ball against its spin and takes away spin
+    let mut ball = Ball::new(100.0, 1.0, 200.0, -200.0);
+    ball.spin = 5.0;
+    ball.update(&args, 0.0..500.0, &config);
+    assert!(ball.y_velocity > 0.0);
+    assert!(ball.x_velocity < 200.0);
+    assert!(ball.spin < 5.0 * config.wall_spin_retention + f64::EPSILON);
+}
+
+#[test]
+fn test_ball_speed_never_exceeds_cap() {
+    let curves = [
+        SpeedCurve::Linear { increase: 50.0 },
+        SpeedCurve::Exponential { multiplier: 1.1 },
+        SpeedCurve::Capped { fraction: 0.2 },
+    ];
+
+    for speed_curve in curves {
+        let mut options = golden_options();
+        options.rng = StdRng::from_entropy();
+        options.config.ball.speed_curve = speed_curve.clone();
+        options.config.ball.rally_speed_up = Some(100.0);
+        let max_speed = options.config.ball.max_speed;
+
+        // A paddle moving at full speed
+        let mut paddle = Paddle::new(0.0, 0.0);
+        paddle.input_mut().press_up();
+        for _ in 0..100 {
+            paddle.update(
+                &UpdateArgs { dt: 0.01 },
+                0.0..10_000.0,
+                &options.config.paddle,
+            );
+        }
+
+        let mut ball = Ball::new(0.0, 0.0, -START_BALL_VELOCITY, 0.0);
+        for _ in 0..1000 {
+            // Hit the ball with the paddle and let it fly for a while
+            ball.x = 0.0;
+            ball.y = 0.0;
+            ball.x_velocity = -ball.x_velocity.abs();
+            PlayState::check_ball_paddle_collide(&PlayerId::One, &paddle, &mut ball, &mut options);
+            assert!(ball.speed() <= max_speed + 1e-9, "{:?}", speed_curve);
+
+            ball.update(&UpdateArgs { dt: 0.1 }, -1e9..1e9, &options.config.ball);
+            assert!(ball.speed() <= max_speed + 1e-9, "{:?}", speed_curve);
+        }
+        // The ball has been sped up all the way
+        assert!(ball.x_velocity.abs() > max_speed * 0.9, "{:?}", speed_curve);
+    }
+}
+
+#[test]
+fn test_court_viewport() {
+    // A wider window than the court centers the court horizontally
+    let viewport = CourtViewport::new([1200.0, 500.0]);
+    assert_eq!(viewport.window_to_court([100.0, 0.0]), Some([0.0, 0.0]));
+    assert_eq!(
+        viewport.window_to_court([1100.0, 500.0]),
+        Some([1000.0, 500.0])
+    );
+
+    // A minimized window has no court to point at
+    let viewport = CourtViewport::new([0.0, 0.0]);
+    assert_eq!(viewport.window_to_court([10.0, 10.0]), None);
+}
+
+#[test]
+fn test_software_renderer_letterbox() {
+    let mut options = golden_options();
+    let mut state = GameState::Play(Box::new(PlayState::new(&mut options)));
+
+    // A wider window than the court adds bars on the left and right
+    let mut renderer = SoftwareRenderer::new(1200, 500);
+    renderer.render(&mut state, &mut options);
+
+    let letterbox = LETTERBOX_COLOR.map(|value| (value * 255.0).round() as u8);
+    assert_eq!(renderer.graphics.pixel(50, 250), letterbox);
+    assert_eq!(renderer.graphics.pixel(1150, 250), letterbox);
+    assert_eq!(renderer.graphics.pixel(150, 450), [0, 0, 0, 255]);
+
+    // The paddle of player one, which is drawn upwards from the center of the court
+    assert_eq!(renderer.graphics.pixel(100 + 60, 225), [255, 255, 255, 255]);
+}
+
+/// The biggest difference of a color channel for a pixel to still match the reference image.
+const GOLDEN_CHANNEL_TOLERANCE: u8 = 2;
+/// The number of pixels that may differ from the reference image.
+/// Kept low enough that a single changed digit of the score fails.
+const GOLDEN_PIXEL_TOLERANCE: usize = 16;
+
+/// Renders the state and compares it with the reference image `tests/golden/<name>.png`.
+/// On a mismatch the rendered image and a diff, where every differing pixel is red,
+/// are written into `target/golden/`. Run the tests with `UPDATE_GOLDEN=1` to replace the references.
+fn assert_golden(name: &str, mut state: GameState, options: &mut GameOptions<HeadlessGameImpl>) {
+    let mut renderer = SoftwareRenderer::new(WINDOW_SIZE.0, WINDOW_SIZE.1);
+    renderer.render(&mut state, options);
+    let actual = &renderer.graphics;
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let reference_path = root.join("tests/golden").join(format!("{}.png", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+        actual.save_png(&reference_path).unwrap();
+        return;
+    }
+
+    let decoder = png::Decoder::new(File::open(&reference_path).unwrap_or_else(|err| {
+        panic!(
+            "Unable to open {}, run the tests with UPDATE_GOLDEN=1 to create it: {}",
+            reference_path.display(),
+            err
+        )
+    }));
+    let mut reader = decoder.read_info().unwrap();
+    let mut expected = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut expected).unwrap();
+    assert_eq!(
+        (info.width, info.height),
+        (actual.width(), actual.height()),
+        "The size of {} doesn't match the reference image",
+        name
+    );
+
+    let mut diff = Vec::with_capacity(expected.len());
+    let mut differing = 0;
+    for (expected, actual) in expected
+        .chunks_exact(4)
+        .zip(actual.pixels().chunks_exact(4))
+    {
+        let matches = expected
+            .iter()
+            .zip(actual)
+            .all(|(expected, actual)| expected.abs_diff(*actual) <= GOLDEN_CHANNEL_TOLERANCE);
+        if matches {
+            // Show the matching pixels darkened to make the differences stand out
+            diff.extend(actual[..3].iter().map(|value| value / 4));
+            diff.push(255);
+        } else {
+            differing += 1;
+            diff.extend([255, 0, 0, 255]);
+        }
+    }
+
+    if differing > GOLDEN_PIXEL_TOLERANCE {
+        let artifacts = root.join("target/golden");
+        fs::create_dir_all(&artifacts).unwrap();
+        let actual_path = artifacts.join(format!("{}.png", name));
+        let diff_path = artifacts.join(format!("{}.diff.png", name));
+        actual.save_png(&actual_path).unwrap();
+        save_png(&diff_path, actual.width(), actual.height(), &diff).unwrap();
+
+        panic!(
+            "{} pixels of {} differ from the reference image, see {} and {}",
+            differing,
+            name,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+/// Options with a fixed seed, so every run renders the same images.
+fn golden_options() -> GameOptions<HeadlessGameImpl> {
+    options_with_audio(NullAudio)
+}
+
+/// The options of golden_options with another audio backend.
+fn options_with_audio<Impl: GameImpl<Rng = StdRng>>(audio: Impl::Audio) -> GameOptions<Impl> {
+    GameOptions {
+        rng: StdRng::seed_from_u64(0),
+        config: Config::default(),
+        audio,
+        events: EventQueue::new(),
+        profiles: ProfileStore::in_memory(),
+        achievements: Achievements::default(),
+    }
+}
+
+/// Dispatches the events like the game does, to the audio, the profiles, the achievements and the given subscribers.
+fn dispatch<Impl: GameImpl>(
+    options: &mut GameOptions<Impl>,
+    dt: f64,
+    extra: &mut [&mut dyn EventSubscriber],
+) -> Vec<GameEvent> {
+    let GameOptions {
+        events,
+        audio,
+        profiles,
+        achievements,
+        ..
+    } = options;
+    let mut subscribers: Vec<&mut dyn EventSubscriber> = vec![audio, profiles, achievements];
+    for subscriber in extra.iter_mut() {
+        subscribers.push(*subscriber);
+    }
+    let events = events.dispatch(dt, &mut subscribers);
+    achievements.unlock_reached(profiles);
+    events
+}
+
+fn countdown(secs: u64) -> GameState {
+    GameState::Countdown(Box::new(CountdownState::new(
+        Duration::from_secs(secs),
+        GameState::Menu(Box::default()),
+    )))
+}
+
+#[test]
+fn test_golden_countdown() {
+    let mut options = golden_options();
+    assert_golden("countdown_3", countdown(3), &mut options);
+    assert_golden("countdown_2", countdown(2), &mut options);
+    assert_golden("countdown_1", countdown(1), &mut options);
+}
+
+#[test]
+fn test_golden_play() {
+    let mut options = golden_options();
+    let mut ball = Ball::new(610.0, 270.0, -300.0, 120.0);
+    ball.rotation = 0.4;
+    let state = PlayState::with_players(
+        Player {
+            paddle: Paddle::new(50.0, 320.0),
+            score: 3,
+            id: PlayerId::One,
+        },
+        Player {
+            paddle: Paddle::new(930.0, 140.0),
+            score: 2,
+            id: PlayerId::Two,
+        },
+        ball,
+        Arena::default(),
+    );
+
+    assert_golden("play", GameState::Play(Box::new(state)), &mut options);
+}
+
+#[test]
+fn test_golden_two_digit_scores() {
+    let mut options = golden_options();
+    let mut state = PlayState::new(&mut options);
+    state.set_score(&PlayerId::One, 10);
+    state.set_score(&PlayerId::Two, 47);
+
+    assert_golden(
+        "two_digit_scores",
+        GameState::Play(Box::new(state)),
+        &mut options,
+    );
+}
+
+#[test]
+fn test_replay_renders_like_the_match() {
+    let mut options = golden_options();
+    let mut state = GameState::Play(Box::new(PlayState::new(&mut options)));
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    let mut replay = Replay::default();
+    for _ in 0..60 {
+        state = match state.update(&args, &mut options) {
+            Ok(state) | Err(state) => state,
+        };
+        replay.record(&state, args.dt);
+    }
+    let mut live = SoftwareRenderer::new(WINDOW_SIZE.0, WINDOW_SIZE.1);
+    live.render(&mut state, &mut options);
+
+    // The next match is played in another arena
+    let arena = Arena {
+        obstacles: vec![Rect::new(400.0, 100.0, 20.0, 300.0)],
+        ..Arena::default()
+    };
+    let mut state = GameState::Play(Box::new(PlayState::with_arena(&mut options, arena.clone())));
+    for _ in 0..30 {
+        state = match state.update(&args, &mut options) {
+            Ok(state) | Err(state) => state,
+        };
+        replay.record(&state, args.dt);
+    }
+    let mut live_arena = SoftwareRenderer::new(WINDOW_SIZE.0, WINDOW_SIZE.1);
+    live_arena.render(&mut state, &mut options);
+
+    // Save and load the replay to make sure nothing gets lost in the file
+    let path = env::temp_dir().join("pong-rs-test-replay.json");
+    replay.save(&path).unwrap();
+    let replay = Replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.frames.len(), 90);
+    assert_eq!(replay.arenas.len(), 2);
+    assert_eq!(replay.arena(59), Some(&Arena::default()));
+    assert_eq!(replay.arena(60), Some(&arena));
+
+    let mut replayed = SoftwareRenderer::new(WINDOW_SIZE.0, WINDOW_SIZE.1);
+    replayed.render(&mut replay.state(59), &mut options);
+    assert!(live.graphics.pixels() == replayed.graphics.pixels());
+    replayed.render(&mut replay.state(89), &mut options);
+    assert!(live_arena.graphics.pixels() == replayed.graphics.pixels());
+}
+
+#[test]
+fn test_terminal_canvas() {
+    let mut options = golden_options();
+    let state = GameState::Play(Box::new(PlayState::new(&mut options)));
+
+    // Every cell is two pixels high, so the court fills the canvas exactly
+    let mut canvas = TerminalCanvas::new(100, 25);
+    canvas.render(&state);
+
+    assert_eq!(canvas.pixel(6, 22), PADDLE_COLOR);
+    assert_eq!(canvas.pixel(6, 30), BACKGROUND_COLOR);
+    assert_eq!(canvas.text(25, 2), Some('0'));
+    assert_eq!(canvas.text(75, 2), Some('0'));
+}
+
+#[test]
+fn test_predict_intercept() {
+    let config = BallConfig::default();
+    let arena = Arena::default();
+
+    // A straight ball reaches the right paddle after moving 390 units
+    let ball = Ball::new(500.0, 250.0, 200.0, 0.0);
+    let intercept = predict_intercept(&ball, 900.0, &arena, &config).unwrap();
+    assert!((intercept.position[1] - 255.0).abs() < 0.001);
+    assert!((intercept.time - 1.95).abs() < 0.01);
+
+    // The ball bounces off the top wall at y 490 before it gets there
+    let ball = Ball::new(500.0, 400.0, 200.0, 200.0);
+    let intercept = predict_intercept(&ball, 900.0, &arena, &config).unwrap();
+    assert!((intercept.position[1] - 195.0).abs() < 2.0);
+
+    // A ball moving to the left reaches the left paddle instead
+    let ball = Ball::new(500.0, 250.0, -200.0, 0.0);
+    let intercept = predict_intercept(&ball, 70.0, &arena, &config).unwrap();
+    assert!((intercept.position[0] - 75.0).abs() < 2.0);
+
+    // An obstacle in the way sends the ball back
+    let arena = Arena {
+        obstacles: vec![Rect::new(600.0, 0.0, 50.0, 500.0)],
+        ..Arena::default()
+    };
+    let ball = Ball::new(500.0, 250.0, 200.0, 0.0);
+    assert!(predict_intercept(&ball, 900.0, &arena, &config).is_none());
+}
+
+#[test]
+fn test_time_control() {
+    let mut time = TimeControl::new();
+    assert_eq!(time.updates(), 1);
+
+    // At the slowest speed only every tenth update event updates the game
+    for _ in 0..3 {
+        time.button_press(&Button::Keyboard(Key::F7));
+    }
+    assert_eq!(time.scale(), 0.1);
+    let updates: u32 = (0..100).map(|_| time.updates()).sum();
+    assert_eq!(updates, 10);
+
+    // Faster speeds do several updates with the same time step instead of bigger ones
+    for _ in 0..10 {
+        time.button_press(&Button::Keyboard(Key::F8));
+    }
+    assert_eq!(time.scale(), 4.0);
+    assert_eq!(time.updates(), 4);
+
+    // Stepping pauses the game and allows a single update
+    time.button_press(&Button::Keyboard(Key::F6));
+    assert!(time.paused);
+    assert_eq!(time.updates(), 1);
+    assert_eq!(time.updates(), 0);
+
+    time.button_press(&Button::Keyboard(Key::F5));
+    assert!(!time.paused);
+    assert_eq!(time.updates(), 4);
+}
+
+#[test]
+fn test_rewind() {
+    let mut options = golden_options();
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    let mut state = GameState::Play(Box::new(PlayState::new(&mut options)));
+    let mut rewind = RewindBuffer::new();
+    rewind.record(&state);
+
+    let mut positions = Vec::new();
+    for _ in 0..10 {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        rewind.record(&state);
+        if let GameState::Play(play) = &state {
+            positions.push(play.ball().x);
+        }
+    }
+    assert_eq!(rewind.len(), 10);
+
+    // Going back 5 updates gives the match after the fifth update
+    let play = rewind.rewind(5).unwrap();
+    assert_eq!(play.ball().x, positions[4]);
+    assert_eq!(rewind.len(), 5);
+
+    // Continuing from there plays the same updates again
+    let mut state = GameState::Play(Box::new(play));
+    state = state
+        .update(&args, &mut options)
+        .unwrap_or_else(|state| state);
+    match &state {
+        GameState::Play(play) => assert_eq!(play.ball().x, positions[5]),
+        _ => panic!("The match has ended!"),
+    }
+
+    // Rewinding too far stops at the oldest update
+    let play = rewind.rewind(1000).unwrap();
+    assert!(play.ball().x < positions[0]);
+    assert!(rewind.rewind(1).is_none());
+
+    // Leaving the match forgets it
+    rewind.record(&GameState::Menu(Box::default()));
+    assert!(rewind.is_empty());
+
+    // The rally before a point can be rewound, but not the one that won the match
+    for points_to_win in [2, 1] {
+        let mut play = PlayState::new(&mut options);
+        play.set_points_to_win(points_to_win);
+        play.set_ball(Ball::new(800.0, 100.0, 200.0, 0.0));
+        let mut state = GameState::Play(Box::new(play));
+        while let GameState::Play(_) = state {
+            state = state
+                .update(&args, &mut options)
+                .unwrap_or_else(|state| state);
+            rewind.record(&state);
+        }
+        let GameState::InstantReplay(replay) = &state else {
+            panic!("Unable to find the replay of the point!");
+        };
+        assert_eq!(replay.ends_match(), points_to_win == 1);
+        assert_eq!(rewind.rewind(1).is_some(), points_to_win == 2);
+        rewind.record(&GameState::Menu(Box::default()));
+    }
+}
+
+#[test]
+fn test_debug_controls() {
+    let mut options = golden_options();
+    let mut state = GameState::Play(Box::new(PlayState::new(&mut options)));
+
+    // Without the controls only the overlay can be shown and nothing is kept for rewinding
+    let mut debug = DebugOverlay::new();
+    for key in [
+        BUTTON_DEBUG_OVERLAY,
+        BUTTON_DEBUG_PAUSE,
+        BUTTON_DEBUG_FASTER,
+    ] {
+        debug.button_press(&Button::Keyboard(key), &mut state);
+    }
+    debug.update(&state);
+    debug.update(&state);
+    assert!(debug.enabled);
+    assert!(!debug.time.paused);
+    assert_eq!(debug.time.scale(), 1.0);
+    assert!(debug.rewind.is_empty());
+
+    // With the controls the match is kept and can be rewound
+    debug.controls = true;
+    debug.update(&state);
+    debug.update(&state);
+    debug.button_press(&Button::Keyboard(BUTTON_DEBUG_REWIND), &mut state);
+    assert!(debug.time.paused);
+    assert!(matches!(state, GameState::Play(_)));
+}
+
+/// Plays a match until the ball went into the goal of player two after a rally of about a second.
+fn score_point(options: &mut GameOptions<HeadlessGameImpl>) -> GameState {
+    let mut play = PlayState::new(options);
+    play.set_ball(Ball::new(800.0, 100.0, 200.0, 0.0));
+
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    let mut state = GameState::Play(Box::new(play));
+    while let GameState::Play(_) = state {
+        state = state.update(&args, options).unwrap_or_else(|state| state);
+    }
+    state
+}
+
+#[test]
+fn test_rally_frames() {
+    let countdown = |remaining: f64| ReplayFrame::Countdown { remaining };
+    let remaining = |frames: Vec<ReplayFrame>| -> Vec<f64> {
+        frames
+            .into_iter()
+            .map(|frame| match frame {
+                ReplayFrame::Countdown { remaining } => remaining,
+                ReplayFrame::Play { .. } => panic!("Unable to find a countdown frame!"),
+            })
+            .collect()
+    };
+
+    // Only the last frames are kept
+    let mut rally = RallyFrames::new();
+    for frame in 0..10 {
+        rally.push(countdown(frame as f64), 3);
+    }
+    assert_eq!(remaining(rally.frames(3)), [7.0, 8.0, 9.0]);
+    assert_eq!(remaining(rally.frames(2)), [8.0, 9.0]);
+
+    // A clone keeps its frames while the original goes on
+    let snapshot = rally.clone();
+    for frame in 10..20 {
+        rally.push(countdown(frame as f64), 3);
+    }
+    assert_eq!(remaining(snapshot.frames(3)), [7.0, 8.0, 9.0]);
+    assert_eq!(remaining(rally.frames(3)), [17.0, 18.0, 19.0]);
+    assert!(RallyFrames::new().frames(3).is_empty());
+}
+
+#[test]
+fn test_instant_replay() {
+    let mut options = golden_options();
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    // The rally is shown in slow motion before the countdown to the next one
+    let mut state = score_point(&mut options);
+    assert!(matches!(state, GameState::InstantReplay(_)));
+    let mut updates = 0;
+    while let GameState::InstantReplay(_) = state {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        updates += 1;
+    }
+    assert!((250..350).contains(&updates));
+    match state {
+        GameState::Countdown(countdown) => assert!(countdown.remaining() > Duration::from_secs(3)),
+        _ => panic!("The replay didn't continue with the countdown!"),
+    }
+
+    // The replay can be skipped
+    let mut state = score_point(&mut options);
+    state.button_press(&Button::Keyboard(Key::Space), &options);
+    state = state
+        .update(&args, &mut options)
+        .unwrap_or_else(|state| state);
+    assert!(matches!(state, GameState::Countdown(_)));
+}
+
+#[test]
+fn test_golden_instant_replay() {
+    let mut options = golden_options();
+    let mut state = score_point(&mut options);
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    for _ in 0..100 {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+    }
+
+    assert_golden("instant_replay", state, &mut options);
+}
+
+/// Records the sounds of the game instead of playing them.
+struct RecordingGameImpl;
+impl GameImpl for RecordingGameImpl {
+    type Rng = StdRng;
+    type GraphicsImpl = SoftwareGraphicsImpl;
+    type Audio = WavRecorder;
+}
+
+#[test]
+fn test_sounds() {
+    let mut options: GameOptions<RecordingGameImpl> = options_with_audio(WavRecorder::new());
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    // The ball bounces off the top and goes into the goal above the paddle
+    let mut play = PlayState::new(&mut options);
+    play.set_ball(Ball::new(800.0, 485.0, 200.0, 100.0));
+    let mut state = GameState::Play(Box::new(play));
+    // Play until the next rally starts after the replay and the countdown
+    let mut scored = false;
+    while !(scored && matches!(state, GameState::Play(_))) {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        dispatch(&mut options, args.dt, &mut []);
+        scored |= !matches!(state, GameState::Play(_));
+    }
+
+    // The countdown ticks for 3, 2, 1 and 0 and when the next rally starts
+    let sounds: Vec<Sound> = options
+        .audio
+        .played()
+        .iter()
+        .map(|(_, sound)| *sound)
+        .collect();
+    let mut expected = vec![Sound::WallBounce, Sound::Score];
+    expected.extend([Sound::CountdownTick; 5]);
+    assert_eq!(sounds, expected);
+
+    // The sounds are mixed in at the time they were played
+    let (time, _) = options.audio.played()[1];
+    let start = (time * 44_100.0) as usize;
+    let samples = options.audio.samples();
+    assert!(samples[..start].iter().any(|sample| *sample != 0.0));
+    assert!(samples[start - 100..start]
+        .iter()
+        .all(|sample| *sample == 0.0));
+    assert!(samples[start..start + 100]
+        .iter()
+        .any(|sample| *sample != 0.0));
+
+    let path = env::temp_dir().join("pong-rs-test-sounds.wav");
+    options.audio.save(&path).unwrap();
+    let reader = hound::WavReader::open(&path).unwrap();
+    assert_eq!(reader.len() as usize, options.audio.samples().len());
+    fs::remove_file(&path).unwrap();
+
+    // A paddle hitting the ball
+    let paddle = Paddle::new(930.0, 240.0);
+    let mut ball = Ball::new(925.0, 250.0, 200.0, 0.0);
+    PlayState::check_ball_paddle_collide(&PlayerId::Two, &paddle, &mut ball, &mut options);
+    dispatch(&mut options, 0.0, &mut []);
+    assert!(matches!(
+        options.audio.played().last().unwrap().1,
+        Sound::PaddleHit { .. }
+    ));
+}
+
+/// Counts the points of the events it is handed.
+#[derive(Default)]
+struct PointCounter {
+    points: [u32; 2],
+}
+
+impl EventSubscriber for PointCounter {
+    fn handle(&mut self, event: &GameEvent) {
+        if let GameEvent::Scored { player, .. } = event {
+            self.points[*player as usize] += 1;
+        }
+    }
+}
+
+#[test]
+fn test_points_to_win() {
+    let mut options = golden_options();
+    assert_eq!(options.config.rules.points_to_win, None);
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    let play_point = |play: PlayState, options: &mut GameOptions<HeadlessGameImpl>| {
+        let mut state = GameState::Play(Box::new(play));
+        while let GameState::Play(_) = state {
+            state = state.update(&args, options).unwrap_or_else(|state| state);
+        }
+        options.events.take()
+    };
+
+    // Without points to win a match never ends
+    let mut play = PlayState::new(&mut options);
+    play.set_score(&PlayerId::One, 99);
+    play.set_ball(Ball::new(800.0, 100.0, 200.0, 0.0));
+    let events = play_point(play, &mut options);
+    assert!(events.contains(&GameEvent::Scored {
+        player: PlayerId::One,
+        score: (100, 0),
+    }));
+    assert!(!events
+        .iter()
+        .any(|event| matches!(event, GameEvent::MatchOver(_))));
+
+    // The points of a single match, like in a tournament, end it without changing the config
+    let mut play = PlayState::new(&mut options);
+    play.set_score(&PlayerId::One, 99);
+    play.set_ball(Ball::new(800.0, 100.0, 200.0, 0.0));
+    play.set_points_to_win(100);
+    let events = play_point(play, &mut options);
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, GameEvent::MatchOver(result) if result.score == (100, 0))));
+}
+
+/// Runs the countdown before the breakout continues.
+fn resume_breakout(
+    mut state: GameState,
+    options: &mut GameOptions<HeadlessGameImpl>,
+) -> BreakoutState {
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    loop {
+        state = match state.update(&args, options).unwrap_or_else(|state| state) {
+            GameState::Breakout(breakout) => return *breakout,
+            state @ GameState::Countdown(_) => state,
+            _ => panic!("Unable to find the breakout after the countdown!"),
+        };
+    }
+}
+
+/// Breaks the first brick with a ball coming from the left.
+fn break_brick(
+    mut breakout: BreakoutState,
+    options: &mut GameOptions<HeadlessGameImpl>,
+) -> Result<BreakoutState, GameState> {
+    let brick = breakout.bricks()[0];
+    breakout.set_ball(Ball::new(brick.x - 9.0, brick.y + 17.5, 200.0, 0.0));
+    breakout.update(&UpdateArgs { dt: 1.0 / 120.0 }, options)
+}
+
+#[test]
+fn test_breakout() {
+    let mut options = golden_options();
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    let mut breakout = BreakoutState::new(&mut options);
+    assert_eq!(
+        (breakout.lives(), breakout.score(), breakout.level()),
+        (3, 0, 1)
+    );
+    assert_eq!(breakout.bricks().len(), 20);
+
+    // Every brick scores for the level, breaking the last one starts the next level
+    for bricks in (1..20).rev() {
+        let Ok(next) = break_brick(breakout, &mut options) else {
+            panic!("The level ended before all bricks were broken!");
+        };
+        breakout = next;
+        assert_eq!(breakout.bricks().len(), bricks);
+    }
+    assert_eq!(breakout.score(), 190);
+    let Err(state @ GameState::Countdown(_)) = break_brick(breakout, &mut options) else {
+        panic!("Unable to find a countdown before the next level!");
+    };
+    let breakout = resume_breakout(state, &mut options);
+    assert_eq!((breakout.score(), breakout.level()), (200, 2));
+    assert_eq!(breakout.bricks().len(), 30);
+    let Ok(breakout) = break_brick(breakout, &mut options) else {
+        panic!("The level ended after the first brick!");
+    };
+    assert_eq!(breakout.score(), 220);
+
+    // Every ball past the paddle costs a life, the last one ends the game
+    let mut breakout = breakout;
+    for lives in (1..3).rev() {
+        breakout.set_ball(Ball::new(-20.0, 300.0, -200.0, 0.0));
+        let Err(state) = breakout.update(&args, &mut options) else {
+            panic!("Unable to find a countdown after losing a life!");
+        };
+        breakout = resume_breakout(state, &mut options);
+        assert_eq!(breakout.lives(), lives);
+    }
+    breakout.set_ball(Ball::new(-20.0, 300.0, -200.0, 0.0));
+    let Ok(breakout) = breakout.update(&args, &mut options) else {
+        panic!("The breakout continued without lives!");
+    };
+    assert!(breakout.is_game_over());
+    assert_eq!((breakout.lives(), breakout.score()), (0, 220));
+    let Ok(mut breakout) = breakout.update(&args, &mut options) else {
+        panic!("The breakout restarted without pressing space!");
+    };
+    assert!(breakout.is_game_over());
+
+    // Space starts over with all lives on the first level
+    breakout.button_press(&Button::Keyboard(Key::Space), &options);
+    let Err(state) = breakout.update(&args, &mut options) else {
+        panic!("The breakout did not restart!");
+    };
+    let breakout = resume_breakout(state, &mut options);
+    assert!(!breakout.is_game_over());
+    assert_eq!(
+        (breakout.lives(), breakout.score(), breakout.level()),
+        (3, 0, 1)
+    );
+    assert_eq!(breakout.bricks().len(), 20);
+}
+
+#[test]
+fn test_events() {
+    let mut options = golden_options();
+    options.config.rules.points_to_win = Some(2);
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    // A paddle hit reports the player and the speed of the ball
+    let paddle = Paddle::new(930.0, 240.0);
+    let mut ball = Ball::new(925.0, 250.0, 200.0, 0.0);
+    PlayState::check_ball_paddle_collide(&PlayerId::Two, &paddle, &mut ball, &mut options);
+    assert_eq!(
+        options.events.events(),
+        [GameEvent::PaddleHit {
+            player: PlayerId::Two,
+            speed: ball.speed(),
+            max_speed: options.config.ball.max_speed,
+        }]
+    );
+    options.events.take();
+
+    // Player two scores twice, which ends the match
+    let mut counter = PointCounter::default();
+    let mut events = Vec::new();
+    let mut play = PlayState::new(&mut options);
+    play.set_score(&PlayerId::Two, 1);
+    play.set_ball(Ball::new(5.0, 250.0, -200.0, 0.0));
+    let mut state = GameState::Play(Box::new(play));
+    while !matches!(state, GameState::Menu(_)) {
+        if let GameState::MatchSummary(_) = state {
+            state.button_press(&Button::Keyboard(Key::Return), &options);
+        }
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        events.extend(dispatch(&mut options, args.dt, &mut [&mut counter]));
+    }
+    assert!(options.events.events().is_empty());
+    assert_eq!(counter.points, [0, 1]);
+
+    let scored = events
+        .iter()
+        .position(|event| {
+            *event
+                == GameEvent::Scored {
+                    player: PlayerId::Two,
+                    score: (0, 2),
+                }
+        })
+        .unwrap();
+    assert_eq!(
+        events[scored + 1],
+        GameEvent::MatchOver(MatchResult {
+            players: [Participant::Guest, Participant::Guest],
+            winner: PlayerId::Two,
+            score: (0, 2),
+            longest_rally: 0,
+        })
+    );
+    assert_eq!(
+        events[scored + 2],
+        GameEvent::StateChanged {
+            from: "Play",
+            to: "InstantReplay",
+        }
+    );
+    assert_eq!(
+        events[events.len() - 2..],
+        [
+            GameEvent::StateChanged {
+                from: "InstantReplay",
+                to: "MatchSummary",
+            },
+            GameEvent::StateChanged {
+                from: "MatchSummary",
+                to: "Menu",
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_match_stats() {
+    let mut stats = MatchStats::new();
+    assert_eq!(stats.average_rally(), None);
+
+    // A rally of three hits that player one wins after 3 seconds
+    stats.advance(0.5);
+    for (player, speed) in [
+        (PlayerId::One, 300.0),
+        (PlayerId::Two, 400.0),
+        (PlayerId::One, 350.0),
+    ] {
+        stats.handle(&GameEvent::PaddleHit {
+            player,
+            speed,
+            max_speed: 800.0,
+        });
+        stats.advance(0.5);
+    }
+    stats.advance(1.0);
+    stats.handle(&GameEvent::Scored {
+        player: PlayerId::One,
+        score: (1, 0),
+    });
+
+    // A rally of a single hit that player one loses after a second
+    stats.handle(&GameEvent::PaddleHit {
+        player: PlayerId::One,
+        speed: 250.0,
+        max_speed: 800.0,
+    });
+    stats.advance(1.0);
+    stats.handle(&GameEvent::Scored {
+        player: PlayerId::Two,
+        score: (1, 1),
+    });
+    // Events without statistics are ignored
+    stats.handle(&GameEvent::WallBounce);
+
+    let one = stats.player(&PlayerId::One);
+    assert_eq!(one.hits, 3);
+    assert_eq!(one.points, 1);
+    assert_eq!(one.fastest_ball, 350.0);
+    // The ball was played by player one for 2 seconds of the first rally and the whole second one
+    assert_eq!(one.possession, 3.0);
+
+    let two = stats.player(&PlayerId::Two);
+    assert_eq!(two.hits, 1);
+    assert_eq!(two.points, 1);
+    assert_eq!(two.fastest_ball, 400.0);
+    assert_eq!(two.possession, 0.5);
+
+    assert_eq!(stats.longest_rally(), 3);
+    assert_eq!(stats.average_rally(), Some(2.0));
+    assert_eq!(
+        stats.rows()[0],
+        ("Points", "1".to_string(), "1".to_string())
+    );
+}
+
+#[test]
+fn test_golden_match_summary() {
+    let mut options = golden_options();
+    options.config.rules.points_to_win = Some(1);
+    let mut state = score_point(&mut options);
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    while let GameState::InstantReplay(_) = state {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+    }
+
+    let GameState::MatchSummary(summary) = &state else {
+        panic!("The match didn't end after the point!");
+    };
+    assert_eq!(summary.title(), "Player one wins 1:0");
+    assert_golden("match_summary", state, &mut options);
+}
+
+/// A new empty directory for a test, removed before the test runs.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pong-rs-{}", name));
+    fs::remove_dir_all(&dir).ok();
+    dir
+}
+
+#[test]
+fn test_profiles() {
+    let dir = test_dir("profiles");
+    let mut profiles = ProfileStore::load(&dir);
+    assert!(profiles.profiles().is_empty());
+
+    profiles.create("Bob").unwrap();
+    profiles.create("Alice").unwrap();
+    assert!(profiles.create("Alice").is_err());
+    for name in [
+        "",
+        "guest",
+        "CPU Hard",
+        "../Alice",
+        " Alice",
+        "A name that is too long",
+    ] {
+        assert!(!Profile::is_valid_name(name), "{:?}", name);
+        assert!(profiles.create(name).is_err(), "{:?}", name);
+    }
+
+    // Alice beats Bob and then a guest
+    let alice = Participant::Profile("Alice".to_string());
+    let result = MatchResult {
+        players: [alice.clone(), Participant::Profile("Bob".to_string())],
+        winner: PlayerId::One,
+        score: (11, 7),
+        longest_rally: 12,
+    };
+    profiles.handle(&GameEvent::MatchOver(result));
+    let result = MatchResult {
+        players: [Participant::Guest, alice],
+        winner: PlayerId::Two,
+        score: (3, 11),
+        longest_rally: 4,
+    };
+    profiles.handle(&GameEvent::MatchOver(result));
+
+    // The profiles are saved after every match
+    let profiles = ProfileStore::load(&dir);
+    let names: Vec<&str> = profiles
+        .profiles()
+        .iter()
+        .map(|profile| profile.name.as_str())
+        .collect();
+    assert_eq!(names, ["Alice", "Bob"]);
+
+    let alice = profiles.get("Alice").unwrap();
+    assert_eq!((alice.wins, alice.losses), (2, 0));
+    assert_eq!((alice.points_won, alice.points_lost), (22, 10));
+    assert_eq!(alice.longest_rally, 12);
+    assert_eq!(alice.head_to_head["Bob"].wins, 1);
+    assert_eq!(alice.head_to_head["Guest"].wins, 1);
+
+    let bob = profiles.get("Bob").unwrap();
+    assert_eq!((bob.wins, bob.losses), (0, 1));
+    assert_eq!(bob.head_to_head["Alice"].losses, 1);
+
+    // A broken profile is moved away instead of stopping the game
+    fs::write(dir.join("Bob.json"), "{ \"name\": ").unwrap();
+    let mut profiles = ProfileStore::load(&dir);
+    assert!(profiles.get("Bob").is_none());
+    assert!(profiles.get("Alice").is_some());
+    assert!(dir.join("Bob.json.broken").exists());
+    profiles.create("Bob").unwrap();
+
+    // Another broken profile with the same name keeps the earlier one
+    fs::write(dir.join("Bob.json"), "{ \"name\": \"Bob\", ").unwrap();
+    let profiles = ProfileStore::load(&dir);
+    assert!(profiles.get("Bob").is_none());
+    assert_eq!(
+        fs::read_to_string(dir.join("Bob.json.broken")).unwrap(),
+        "{ \"name\": "
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("Bob.json.broken.1")).unwrap(),
+        "{ \"name\": \"Bob\", "
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_achievements() {
+    let duplicate = r#"[
+        { "id": "win", "name": "Win", "description": "", "condition": { "rally": { "min_hits": 2 } } },
+        { "id": "win", "name": "Win again", "description": "", "condition": { "rally": { "min_hits": 3 } } }
+    ]"#;
+    assert!(Achievements::parse(duplicate).is_err());
+    assert!(!Achievements::default().definitions().is_empty());
+
+    let dir = test_dir("achievements");
+    let mut options = golden_options();
+    options.profiles = ProfileStore::load(&dir);
+    options.profiles.create("Alice").unwrap();
+    let alice = Participant::Profile("Alice".to_string());
+    let play = |options: &mut GameOptions<HeadlessGameImpl>, events: Vec<GameEvent>| {
+        for event in events {
+            options.events.push(event);
+        }
+        dispatch(options, 0.0, &mut []);
+    };
+
+    // The top speed depends on the max speed of the config
+    play(
+        &mut options,
+        vec![
+            GameEvent::MatchStarted {
+                players: [alice.clone(), Participant::Guest],
+            },
+            GameEvent::PaddleHit {
+                player: PlayerId::One,
+                speed: 800.0,
+                max_speed: 1200.0,
+            },
+        ],
+    );
+    assert!(options.achievements.toast().is_none());
+
+    // Alice against a guest: a fast return, a long rally and a win after being 5 points behind
+    let mut events = vec![GameEvent::MatchStarted {
+        players: [alice.clone(), Participant::Guest],
+    }];
+    events.push(GameEvent::PaddleHit {
+        player: PlayerId::One,
+        speed: 799.9,
+        max_speed: 800.0,
+    });
+    for hit in 0..20 {
+        let player = if hit % 2 == 0 {
+            PlayerId::Two
+        } else {
+            PlayerId::One
+        };
+        events.push(GameEvent::PaddleHit {
+            player,
+            speed: 400.0,
+            max_speed: 800.0,
+        });
+    }
+    // The first points were rewound, only the score of the events counts
+    events.extend((1..=5).map(|points| GameEvent::Scored {
+        player: PlayerId::One,
+        score: (points, 0),
+    }));
+    events.extend((1..=5).map(|points| GameEvent::Scored {
+        player: PlayerId::Two,
+        score: (0, points),
+    }));
+    events.extend((1..=11).map(|points| GameEvent::Scored {
+        player: PlayerId::One,
+        score: (points, 5),
+    }));
+    events.push(GameEvent::MatchOver(MatchResult {
+        players: [alice.clone(), Participant::Guest],
+        winner: PlayerId::One,
+        score: (11, 5),
+        longest_rally: 21,
+    }));
+    play(&mut options, events);
+
+    let unlocked = |options: &GameOptions<HeadlessGameImpl>| -> Vec<String> {
+        let profile = options.profiles.get("Alice").unwrap();
+        profile.achievements.iter().cloned().collect()
+    };
+    assert_eq!(
+        unlocked(&options),
+        ["comeback", "first_win", "marathon", "top_speed"]
+    );
+
+    // The toasts are shown one after another, in the order they were unlocked
+    let toast = options.achievements.toast().unwrap();
+    assert_eq!(toast.profile, "Alice");
+    assert_eq!(toast.name, "Top speed");
+    dispatch(&mut options, ACHIEVEMENT_TOAST_TIME / 2.0, &mut []);
+    assert_eq!(options.achievements.toast().unwrap().name, "Top speed");
+    dispatch(&mut options, ACHIEVEMENT_TOAST_TIME / 2.0, &mut []);
+    assert_eq!(options.achievements.toast().unwrap().name, "Marathon");
+    for _ in 0..3 {
+        dispatch(&mut options, ACHIEVEMENT_TOAST_TIME, &mut []);
+    }
+    assert!(options.achievements.toast().is_none());
+
+    // A shutout as player two unlocks only the new achievement
+    play(
+        &mut options,
+        vec![
+            GameEvent::MatchStarted {
+                players: [Participant::Guest, alice.clone()],
+            },
+            GameEvent::MatchOver(MatchResult {
+                players: [Participant::Guest, alice.clone()],
+                winner: PlayerId::Two,
+                score: (0, 11),
+                longest_rally: 3,
+            }),
+        ],
+    );
+    assert_eq!(options.achievements.toast().unwrap().name, "Shutout");
+    dispatch(&mut options, ACHIEVEMENT_TOAST_TIME, &mut []);
+    assert!(options.achievements.toast().is_none());
+
+    // Guests and events outside of a match unlock nothing
+    play(
+        &mut options,
+        vec![
+            GameEvent::PaddleHit {
+                player: PlayerId::One,
+                speed: 1000.0,
+                max_speed: 1000.0,
+            },
+            GameEvent::MatchStarted {
+                players: [Participant::Guest, Participant::Guest],
+            },
+            GameEvent::MatchOver(MatchResult {
+                players: [Participant::Guest, Participant::Guest],
+                winner: PlayerId::One,
+                score: (11, 0),
+                longest_rally: 3,
+            }),
+        ],
+    );
+    assert!(options.achievements.toast().is_none());
+
+    // The achievements are saved with the profile
+    let profiles = ProfileStore::load(&dir);
+    let alice = profiles.get("Alice").unwrap();
+    assert_eq!(alice.achievements.len(), 5);
+    assert!(alice.achievements.contains("shutout"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_golden_achievement_toast() {
+    let mut options = golden_options();
+    let mut state = PlayState::new(&mut options);
+    state.set_score(&PlayerId::One, 11);
+    state.set_score(&PlayerId::Two, 0);
+    state.set_ball(Ball::new(610.0, 270.0, -300.0, 120.0));
+    options.achievements.show("Alice", "shutout");
+
+    assert_golden(
+        "achievement_toast",
+        GameState::Play(Box::new(state)),
+        &mut options,
+    );
+}
+
+/// Presses and releases the keys one after another.
+fn press(state: &mut GameState, options: &GameOptions<HeadlessGameImpl>, keys: &[Key]) {
+    for key in keys {
+        state.button_press(&Button::Keyboard(*key), options);
+        state.button_release(&Button::Keyboard(*key), options);
+    }
+}
+
+#[test]
+fn test_profile_select() {
+    let mut options = golden_options();
+    options.profiles.create("Alice").unwrap();
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+    let mut state = GameState::ProfileSelect(Box::default());
+
+    // Create a new profile named Bo
+    press(&mut state, &options, &[Key::Down, Key::Down, Key::Return]);
+    state.button_press(&Button::Keyboard(Key::LShift), &options);
+    press(&mut state, &options, &[Key::B]);
+    state.button_release(&Button::Keyboard(Key::LShift), &options);
+    press(
+        &mut state,
+        &options,
+        &[Key::O, Key::X, Key::Backspace, Key::Return],
+    );
+    let Ok(mut state) = state.update(&args, &mut options) else {
+        panic!("The profile selection ended!");
+    };
+    assert!(options.profiles.get("Bo").is_some());
+
+    // The new profile went to player one, player two chooses Alice
+    press(&mut state, &options, &[Key::Up, Key::Right]);
+    let GameState::ProfileSelect(select) = &state else {
+        unreachable!()
+    };
+    assert_eq!(
+        select.chosen(),
+        &[
+            Participant::Profile("Bo".to_string()),
+            Participant::Profile("Alice".to_string())
+        ]
+    );
+
+    press(&mut state, &options, &[Key::Down, Key::Down, Key::Return]);
+    let Err(GameState::Countdown(_)) = state.update(&args, &mut options) else {
+        panic!("The match didn't start!");
+    };
+}
+
+#[test]
+fn test_versus_match() {
+    let mut options = golden_options();
+    options.profiles.create("Alice").unwrap();
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    // Alice plays a guest, chosen from the menu
+    let mut state = GameState::Menu(Box::default());
+    press(&mut state, &options, &[Key::Return]);
+    let Err(mut state) = state.update(&args, &mut options) else {
+        panic!("The menu didn't open the profile selection!");
+    };
+    press(
+        &mut state,
+        &options,
+        &[Key::Right, Key::Down, Key::Down, Key::Down, Key::Return],
+    );
+
+    // Without points to win in the config the match still ends and counts for the profile
+    assert_eq!(options.config.rules.points_to_win, None);
+    let mut match_over = None;
+    for _ in 0..1_000_000 {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        for event in dispatch(&mut options, args.dt, &mut []) {
+            if let GameEvent::MatchOver(result) = event {
+                assert!(match_over.is_none());
+                match_over = Some(result);
+            }
+        }
+        if let GameState::MatchSummary(_) = state {
+            break;
+        }
+    }
+    let Some(result) = match_over else {
+        panic!("The versus match never ended!");
+    };
+    assert!(result.score.0 == 11 || result.score.1 == 11);
+    let alice = options.profiles.get("Alice").unwrap();
+    assert_eq!(alice.wins + alice.losses, 1);
+}
+#[test]
+fn test_ratings() {
+    let alice = Participant::Profile("Alice".to_string());
+    let bob = Participant::Profile("Bob".to_string());
+    let result = |one: &Participant, two: &Participant, winner| MatchResult {
+        players: [one.clone(), two.clone()],
+        winner,
+        score: (0, 0),
+        longest_rally: 0,
+    };
+
+    assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+    assert!((expected_score(1900.0, 1500.0) - 0.909).abs() < 0.001);
+
+    // Two equal players exchange half of the K factor
+    let history = vec![
+        result(&alice, &bob, PlayerId::One),
+        result(&bob, &Participant::Guest, PlayerId::One),
+        result(&Participant::Bot(Difficulty::Hard), &bob, PlayerId::Two),
+    ];
+    let ratings = Ratings::compute(&history[..1]);
+    assert_eq!(ratings.rating(&alice), 1516.0);
+    assert_eq!(ratings.rating(&bob), 1484.0);
+
+    // Matches against guests are not rated, an upset against a better player gains more
+    let ratings = Ratings::compute(&history);
+    let hard = Participant::Bot(Difficulty::Hard);
+    assert!(ratings.rating(&bob) - 1484.0 > 16.0);
+    assert_eq!(
+        ratings.rating(&bob) - 1484.0,
+        Difficulty::Hard.initial_rating() - ratings.rating(&hard)
+    );
+    assert_eq!(ratings.get(&bob).unwrap().wins, 1);
+    assert_eq!(ratings.get(&Participant::Guest), None);
+
+    // The same history always gives the same ratings
+    assert_eq!(Ratings::compute(&history), ratings);
+    let mut incremental = Ratings::default();
+    for result in &history {
+        incremental.record(result);
+    }
+    assert_eq!(incremental, ratings);
+
+    let leaderboard: Vec<&str> = ratings
+        .leaderboard()
+        .iter()
+        .map(|(participant, _)| participant.name())
+        .collect();
+    assert_eq!(
+        leaderboard,
+        ["CPU Hard", "Alice", "Bob", "CPU Medium", "CPU Easy"]
+    );
+    assert_eq!(ratings.recommend_bot(&alice), Difficulty::Medium);
+    assert_eq!(
+        Ratings::default().recommend_bot(&Participant::Guest),
+        Difficulty::Medium
+    );
+
+    // The history is saved with every match, a broken line only loses that match
+    let dir = test_dir("ratings");
+    let mut profiles = ProfileStore::load(&dir);
+    for result in &history {
+        profiles.record_match(result).unwrap();
+    }
+    let path = dir.join("history.jsonl");
+    let mut file = fs::read_to_string(&path).unwrap();
+    file.insert_str(0, "{ broken\n");
+    fs::write(&path, file).unwrap();
+    let profiles = ProfileStore::load(&dir);
+    assert_eq!(profiles.history(), history);
+    assert_eq!(profiles.ratings(), &ratings);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_bots() {
+    let mut options = golden_options();
+    options.config.rules.points_to_win = Some(3);
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    let mut play = PlayState::new(&mut options);
+    play.set_participants([
+        Participant::Bot(Difficulty::Easy),
+        Participant::Bot(Difficulty::Hard),
+    ]);
+    let mut stats = MatchStats::new();
+    let mut state = GameState::Play(Box::new(play));
+    let result = loop {
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        if let Some(GameEvent::MatchOver(result)) =
+            dispatch(&mut options, args.dt, &mut [&mut stats])
+                .into_iter()
+                .find(|event| matches!(event, GameEvent::MatchOver(_)))
+        {
+            break result;
+        }
+    };
+
+    // Both computer players return the ball, the better one wins
+    assert!(stats.player(&PlayerId::One).hits > 0);
+    assert!(stats.player(&PlayerId::Two).hits > 0);
+    assert_eq!(result.winner, PlayerId::Two);
+    assert_eq!(result.score.1, 3);
+}
+
+/// Plays a whole tournament in which the entrant with the better seed always wins.
+fn play_tournament(format: Format, players: usize) -> Tournament {
+    let entrants = vec![Participant::Bot(Difficulty::Easy); players];
+    let mut tournament = Tournament::new(format, entrants).unwrap();
+    while let Some(game) = tournament.next_match() {
+        let winner = if game.players[0] < game.players[1] {
+            PlayerId::One
+        } else {
+            PlayerId::Two
+        };
+        tournament.record(winner, (1, 0));
+    }
+    tournament
+}
+
+#[test]
+fn test_tournament() {
+    for players in [2, 3, 17] {
+        let entrants = vec![Participant::Guest; players];
+        assert!(Tournament::new(Format::RoundRobin, entrants).is_err());
+    }
+
+    for players in 4..=16 {
+        // Everyone but the champion is out after the first loss
+        let single = play_tournament(Format::SingleElimination, players);
+        assert_eq!(single.matches().len(), players - 1);
+        assert_eq!(single.champion(), Some(0));
+        assert!((1..players).all(|entrant| single.standing(entrant).losses == 1));
+
+        // Or after the second one
+        let double = play_tournament(Format::DoubleElimination, players);
+        assert_eq!(double.matches().len(), 2 * players - 2);
+        assert_eq!(double.champion(), Some(0));
+        assert!((1..players).all(|entrant| double.standing(entrant).losses == 2));
+        assert_eq!(double.matches().last().unwrap().stage, Stage::GrandFinal);
+
+        // Everyone plays everyone once and at most once per round
+        let round_robin = play_tournament(Format::RoundRobin, players);
+        assert_eq!(round_robin.matches().len(), players * (players - 1) / 2);
+        let mut pairs: Vec<[usize; 2]> = round_robin
+            .matches()
+            .iter()
+            .map(|game| {
+                let [one, two] = game.players;
+                [one.min(two), one.max(two)]
+            })
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), round_robin.matches().len());
+        for round in 1..=round_robin.round() {
+            let mut playing: Vec<usize> = round_robin
+                .matches()
+                .iter()
+                .filter(|game| game.round == round)
+                .flat_map(|game| game.players)
+                .collect();
+            let len = playing.len();
+            playing.sort();
+            playing.dedup();
+            assert_eq!(playing.len(), len);
+        }
+        assert_eq!(round_robin.ranking(), (0..players).collect::<Vec<_>>());
+    }
+
+    // The best seeds skip the first round until a power of two is left
+    let tournament = Tournament::new(
+        Format::SingleElimination,
+        vec![Participant::Bot(Difficulty::Hard); 6],
+    )
+    .unwrap();
+    let players: Vec<[usize; 2]> = tournament
+        .matches()
+        .iter()
+        .map(|game| game.players)
+        .collect();
+    assert_eq!(players, [[2, 5], [3, 4]]);
+    assert_eq!(tournament.name(5), "CPU Hard 6");
+
+    // The grand final is played again if the winner of the losers bracket wins it
+    let entrants = ["A", "B", "C", "D"]
+        .map(|name| Participant::Profile(name.to_string()))
+        .to_vec();
+    let mut tournament = Tournament::new(Format::DoubleElimination, entrants).unwrap();
+    while let Some(game) = tournament.next_match() {
+        let winner = match game.stage {
+            Stage::GrandFinal => PlayerId::Two,
+            _ if game.players[0] < game.players[1] => PlayerId::One,
+            _ => PlayerId::Two,
+        };
+        tournament.record(winner, (0, 1));
+    }
+    let finals = tournament
+        .matches()
+        .iter()
+        .filter(|game| game.stage == Stage::GrandFinal)
+        .count();
+    assert_eq!(finals, 2);
+    assert_eq!(tournament.matches().len(), 7);
+    assert_eq!(tournament.name(tournament.champion().unwrap()), "B");
+    assert!(tournament.is_eliminated(0));
+}
+
+#[test]
+fn test_golden_bracket() {
+    let mut options = golden_options();
+    let mut entrants: Vec<Participant> = ["Alice", "Bob", "Carol"]
+        .map(|name| Participant::Profile(name.to_string()))
+        .to_vec();
+    entrants.extend(Difficulty::ALL.map(Participant::Bot));
+    let mut tournament = Tournament::new(Format::DoubleElimination, entrants).unwrap();
+    for (winner, score) in [
+        (PlayerId::One, (11, 4)),
+        (PlayerId::Two, (9, 11)),
+        (PlayerId::One, (11, 7)),
+        (PlayerId::Two, (3, 11)),
+    ] {
+        tournament.record(winner, score);
+    }
+    let state = GameState::Bracket(Box::new(BracketState::new(tournament)));
+    assert_golden("bracket", state, &mut options);
+}
+
+#[test]
+fn test_tournament_play() {
+    let mut options = golden_options();
+    options.config.rules.points_to_win = Some(1);
+    options.profiles.create("Alice").unwrap();
+    let args = UpdateArgs { dt: 1.0 / 120.0 };
+
+    // Enter Alice, a profile can only be entered once, computer players more often
+    let mut state = GameState::TournamentSetup(Box::default());
+    press(
+        &mut state,
+        &options,
+        &[Key::Down, Key::Return, Key::Return, Key::Right, Key::Return],
+    );
+    press(
+        &mut state,
+        &options,
+        &[Key::Right, Key::Return, Key::Return],
+    );
+    let GameState::TournamentSetup(setup) = &state else {
+        unreachable!()
+    };
+    assert_eq!(
+        setup.entrants(),
+        [
+            Participant::Profile("Alice".to_string()),
+            Participant::Bot(Difficulty::Easy),
+            Participant::Bot(Difficulty::Medium),
+            Participant::Bot(Difficulty::Medium),
+        ]
+    );
+    // Alice is controlled from the keyboard, so she loses by standing still
+    press(&mut state, &options, &[Key::Down, Key::Down, Key::Return]);
+    let Err(mut state) = state.update(&args, &mut options) else {
+        panic!("The tournament didn't start!");
+    };
+
+    let mut results = Vec::new();
+    let champion = loop {
+        match &state {
+            GameState::Bracket(bracket) if bracket.tournament().is_over() => {
+                break bracket.title();
+            }
+            GameState::Bracket(_) | GameState::MatchSummary(_) => {
+                press(&mut state, &options, &[Key::Return]);
+            }
+            _ => {}
+        }
+        state = state
+            .update(&args, &mut options)
+            .unwrap_or_else(|state| state);
+        for event in dispatch(&mut options, args.dt, &mut []) {
+            if let GameEvent::MatchOver(result) = event {
+                results.push(result);
+            }
+        }
+    };
+
+    // Every match counts for the profiles and the ratings
+    assert_eq!(results.len(), 3);
+    assert_eq!(options.profiles.history(), results);
+    assert_eq!(options.profiles.get("Alice").unwrap().losses, 1);
+    assert!(champion.starts_with("CPU"));
+    assert!(champion.ends_with("wins the tournament"));
+}
+
+#[test]
+fn test_bench() {
+    let (low, high) = wilson_interval(0.5, 10);
+    assert!((low - 0.237).abs() < 0.001 && (high - 0.763).abs() < 0.001);
+    assert_eq!(wilson_interval(1.0, 10).1, 1.0);
+    assert!(wilson_interval(0.0, 10).0 == 0.0 && wilson_interval(0.0, 10).1 > 0.0);
+
+    // Controllers are named after their file without a name, broken ones are errors
+    let dir = test_dir("bench");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("hard.json"),
+        r#"{ "name": "Hard", "controller": { "builtin": "Hard" } }"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("sleepy.json"),
+        r#"{ "controller": { "scripted": { "reaction_time": 2.0, "aim_error": 150.0 } } }"#,
+    )
+    .unwrap();
+    fs::write(dir.join("notes.txt"), "Not a controller").unwrap();
+    let controllers = load_controllers(&dir).unwrap();
+    let names: Vec<&str> = controllers.iter().map(|bot| bot.name.as_str()).collect();
+    assert_eq!(names, ["Hard", "sleepy"]);
+    for broken in [
+        r#"{ "controller": { "scripted": { "reaction_time": -1.0, "aim_error": 0.0 } } }"#,
+        r#"{ "controller": { "linear": { "weights": [1.0] } } }"#,
+    ] {
+        fs::write(dir.join("broken.json"), broken).unwrap();
+        let err = load_controllers(&dir).unwrap_err();
+        assert!(err.to_string().contains("broken.json"));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    // The same seed always plays the same match
+    let settings = BenchSettings {
+        matches: 2,
+        points_to_win: 1,
+        ..BenchSettings::default()
+    };
+    let [hard, sleepy] = [&controllers[0].controller, &controllers[1].controller];
+    assert_eq!(
+        play_match(hard, sleepy, &settings, 7),
+        play_match(hard, sleepy, &settings, 7)
+    );
+
+    // Both sides of a pair are recorded, once on each side of the court
+    let crosstable = Crosstable::run(&controllers, &settings);
+    assert_eq!(crosstable.get(0, 1).games(), 2);
+    assert_eq!(crosstable.get(0, 1).wins, crosstable.get(1, 0).losses);
+    assert_eq!(crosstable.get(0, 1).draws, crosstable.get(1, 0).draws);
+    assert_eq!(crosstable.total(0), *crosstable.get(0, 1));
+    assert_eq!(crosstable, Crosstable::run(&controllers, &settings));
+    let table = crosstable.to_string();
+    assert_eq!(table.lines().count(), 3);
+    assert!(table.lines().next().unwrap().ends_with("Total"));
+}
+
+/// A tone of 441 hertz, which has a period of exactly 100 samples, without fading in or out.
+fn flat_tone(waveform: Waveform) -> Tone {
+    Tone {
+        waveform,
+        frequency: 441.0,
+        end_frequency: 441.0,
+        duration: 0.1,
+        envelope: Envelope {
+            attack: 0.0,
+            decay: 0.0,
+            sustain: 1.0,
+            release: 0.0,
+        },
+        volume: 0.5,
+    }
+}
+
+/// The number of times the samples change from negative to positive or back.
+fn sign_changes(samples: &[f32]) -> usize {
+    samples
+        .windows(2)
+        .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+        .count()
+}
+
+#[test]
+fn test_synth_waveforms() {
+    let square = flat_tone(Waveform::Square).render();
+    assert_eq!(square.len(), 4410);
+    assert!(square[..50].iter().all(|sample| *sample == 0.5));
+    assert!(square[50..100].iter().all(|sample| *sample == -0.5));
+    assert!((sign_changes(&square) as i32 - 88).abs() <= 1);
+
+    // The triangle goes from the bottom at the start of a period to the top in the middle
+    let triangle = flat_tone(Waveform::Triangle).render();
+    assert!((triangle[0] + 0.5).abs() < 0.001);
+    assert!(triangle[25].abs() < 0.001);
+    assert!((triangle[50] - 0.5).abs() < 0.001);
+    assert!((triangle[75]).abs() < 0.001);
+
+    // The noise is the same every time and jumps between both sides
+    let noise_tone = Tone {
+        frequency: 4410.0,
+        end_frequency: 4410.0,
+        ..flat_tone(Waveform::Noise)
+    };
+    let noise = noise_tone.render();
+    assert_eq!(noise, noise_tone.render());
+    assert!(noise.iter().all(|sample| sample.abs() == 0.5));
+    let mean = noise.iter().sum::<f32>() / noise.len() as f32;
+    assert!(mean.abs() < 0.2);
+    assert!(sign_changes(&noise) > 100);
+}
+
+#[test]
+fn test_synth_envelope() {
+    let envelope = Envelope {
+        attack: 0.1,
+        decay: 0.1,
+        sustain: 0.5,
+        release: 0.2,
+    };
+    assert_eq!(envelope.level(0.0, 1.0), 0.0);
+    assert!((envelope.level(0.05, 1.0) - 0.5).abs() < 0.001);
+    assert!((envelope.level(0.1, 1.0) - 1.0).abs() < 0.001);
+    assert!((envelope.level(0.15, 1.0) - 0.75).abs() < 0.001);
+    assert!((envelope.level(0.5, 1.0) - 0.5).abs() < 0.001);
+    assert!((envelope.level(1.1, 1.0) - 0.25).abs() < 0.001);
+    assert!(envelope.level(1.2, 1.0).abs() < 0.001);
+
+    // A tone released during the attack fades out from where it was
+    assert!((envelope.level(0.15, 0.05) - 0.25).abs() < 0.001);
+}
+
+#[test]
+fn test_paddle_hit_pitch() {
+    let slow = Sound::PaddleHit { speed: 200.0 }.samples();
+    let fast = Sound::PaddleHit { speed: 800.0 }.samples();
+    assert_eq!(slow.len(), fast.len());
+    // Four times the speed gives twice the frequency
+    let ratio = sign_changes(&fast) as f64 / sign_changes(&slow) as f64;
+    assert!((ratio - 2.0).abs() < 0.1);
+
+    // The sounds survive a round trip through a WAV file
+    let path = env::temp_dir().join("pong-rs-test-hit.wav");
+    save_wav(&path, &fast).unwrap();
+    let samples: Vec<i16> = hound::WavReader::open(&path)
+        .unwrap()
+        .into_samples()
+        .map(|sample| sample.unwrap())
+        .collect();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(samples.len(), fast.len());
+    assert_eq!(
+        sign_changes(
+            &samples
+                .iter()
+                .map(|sample| *sample as f32)
+                .collect::<Vec<_>>()
+        ),
+        sign_changes(&fast)
+    );
+}
+
+fn settings_display(state: &GameState) -> &DisplayConfig {
+    let GameState::Settings(settings) = state else {
+        panic!("The settings were closed!");
+    };
+    settings.display()
+}
+
+#[test]
+fn test_settings() {
+    let options = golden_options();
+    let mut state = GameState::Settings(Box::new(SettingsState::new(&options.config)));
+
+    // The display modes cycle in both directions and wrap around
+    press(&mut state, &options, &[Key::Right]);
+    assert_eq!(settings_display(&state).mode, DisplayMode::Borderless);
+    press(&mut state, &options, &[Key::Return]);
+    assert_eq!(settings_display(&state).mode, DisplayMode::Exclusive);
+    press(&mut state, &options, &[Key::Right]);
+    assert_eq!(settings_display(&state).mode, DisplayMode::Windowed);
+    press(&mut state, &options, &[Key::Left]);
+    assert_eq!(settings_display(&state).mode, DisplayMode::Exclusive);
+
+    // Vsync toggles and the frame rate cap cycles through the caps
+    press(&mut state, &options, &[Key::Down, Key::Right]);
+    assert!(settings_display(&state).vsync);
+    press(&mut state, &options, &[Key::Left]);
+    assert!(!settings_display(&state).vsync);
+    press(&mut state, &options, &[Key::Down, Key::Left]);
+    assert_eq!(settings_display(&state).max_fps, 30);
+    press(&mut state, &options, &[Key::Left]);
+    assert_eq!(settings_display(&state).max_fps, 240);
+    press(&mut state, &options, &[Key::Right, Key::Right]);
+    assert_eq!(settings_display(&state).max_fps, 60);
+
+    // A cap that isn't in the list continues with the closest one
+    let mut config = Config::default();
+    config.display.max_fps = 100;
+    let mut state = GameState::Settings(Box::new(SettingsState::new(&config)));
+    press(&mut state, &options, &[Key::Down, Key::Down, Key::Right]);
+    assert_eq!(settings_display(&state).max_fps, 144);
+}
+
+#[test]
+fn test_config_file() {
+    let options = golden_options();
+    let dir = test_dir("config");
+    let path = dir.join("config.json");
+
+    // The config survives a round trip through the file
+    let mut config = Config::default();
+    config.display.mode = DisplayMode::Exclusive;
+    config.display.vsync = true;
+    config.display.max_fps = 144;
+    config.rules.points_to_win = Some(5);
+    config.save(&path).unwrap();
+    assert_eq!(Config::load(&path).unwrap(), config);
+
+    // Missing values use the default values
+    fs::write(&path, r#"{ "display": { "vsync": true } }"#).unwrap();
+    let loaded = Config::load(&path).unwrap();
+    assert!(loaded.display.vsync);
+    assert_eq!(loaded.display.mode, DisplayMode::Windowed);
+    assert_eq!(loaded.display.max_fps, Config::default().display.max_fps);
+
+    // The mode of --fullscreen is used for the run, but the file keeps the mode of the settings
+    let mut config = Config::default();
+    config.display.mode_override = DisplayMode::from_fullscreen_arg(None);
+    let mut state = GameState::Settings(Box::new(SettingsState::new(&config)));
+    press(&mut state, &options, &[Key::Down, Key::Right]);
+    config.display = settings_display(&state).clone();
+    assert_eq!(config.display.current_mode(), DisplayMode::Borderless);
+    config.save(&path).unwrap();
+    let loaded = Config::load(&path).unwrap();
+    assert_eq!(loaded.display.current_mode(), DisplayMode::Windowed);
+    assert!(loaded.display.vsync);
+
+    // Choosing a mode in the settings replaces the one of the command line and is saved
+    let mut state = GameState::Settings(Box::new(SettingsState::new(&config)));
+    press(&mut state, &options, &[Key::Right]);
+    assert_eq!(settings_display(&state).mode, DisplayMode::Exclusive);
+    assert_eq!(settings_display(&state).mode_override, None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fullscreen_arg() {
+    assert_eq!(
+        DisplayMode::from_fullscreen_arg(None),
+        Some(DisplayMode::Borderless)
+    );
+    assert_eq!(
+        DisplayMode::from_fullscreen_arg(Some("windowed")),
+        Some(DisplayMode::Windowed)
+    );
+    assert_eq!(
+        DisplayMode::from_fullscreen_arg(Some("exclusive")),
+        Some(DisplayMode::Exclusive)
+    );
+    assert_eq!(DisplayMode::from_fullscreen_arg(Some("tiled")), None);
+}
@@ -0,0 +1,313 @@
+use std::cmp::Reverse;
+use std::io;
+
+use crate::game_state::play::PlayerId;
+use crate::profile::Participant;
+use crate::{TOURNAMENT_MAX_PLAYERS, TOURNAMENT_MIN_PLAYERS};
+
+/// How the matches of a tournament are paired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A player is out after the first loss.
+    SingleElimination,
+    /// A player drops into the losers bracket after the first loss and is out after the second.
+    DoubleElimination,
+    /// Everyone plays everyone once, the most wins take the tournament.
+    RoundRobin,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [
+        Format::SingleElimination,
+        Format::DoubleElimination,
+        Format::RoundRobin,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::SingleElimination => "Single elimination",
+            Format::DoubleElimination => "Double elimination",
+            Format::RoundRobin => "Round robin",
+        }
+    }
+
+    /// The losses after which a player is out of the tournament.
+    fn max_losses(&self) -> Option<u32> {
+        match self {
+            Format::SingleElimination => Some(1),
+            Format::DoubleElimination => Some(2),
+            Format::RoundRobin => None,
+        }
+    }
+}
+
+/// The part of the tournament a match belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// The winners bracket of an elimination tournament or a round of a round robin.
+    Main,
+    /// The bracket of the players with one loss in a double elimination.
+    Losers,
+    /// The last match of a double elimination, between the winners of both brackets.
+    GrandFinal,
+}
+
+/// A single match of a tournament.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentMatch {
+    /// The round the match is played in, starting at 1.
+    pub round: u32,
+    pub stage: Stage,
+    /// The entrants playing player one and two.
+    pub players: [usize; 2],
+    /// The winner and the final score, once the match has been played.
+    pub result: Option<(PlayerId, (u32, u32))>,
+}
+
+impl TournamentMatch {
+    /// The entrant that won the match.
+    pub fn winner(&self) -> Option<usize> {
+        self.result.map(|(winner, _)| match winner {
+            PlayerId::One => self.players[0],
+            PlayerId::Two => self.players[1],
+        })
+    }
+}
+
+/// The record of an entrant in the tournament.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Standing {
+    pub wins: u32,
+    pub losses: u32,
+    pub points_won: u32,
+    pub points_lost: u32,
+}
+
+/// A tournament between local profiles and computer players.
+/// The matches of a round are paired once the previous round is over,
+/// so an elimination bracket only depends on the results so far.
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    format: Format,
+    /// In the order of their seeds, the first entrants get the byes.
+    entrants: Vec<Participant>,
+    matches: Vec<TournamentMatch>,
+    standings: Vec<Standing>,
+}
+
+impl Tournament {
+    pub fn new(format: Format, entrants: Vec<Participant>) -> io::Result<Self> {
+        if !(TOURNAMENT_MIN_PLAYERS..=TOURNAMENT_MAX_PLAYERS).contains(&entrants.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "A tournament needs {} to {} players",
+                    TOURNAMENT_MIN_PLAYERS, TOURNAMENT_MAX_PLAYERS
+                ),
+            ));
+        }
+
+        let mut tournament = Self {
+            format,
+            standings: vec![Standing::default(); entrants.len()],
+            entrants,
+            matches: Vec::new(),
+        };
+        match format {
+            Format::RoundRobin => tournament.schedule_round_robin(),
+            _ => tournament.schedule_round(),
+        }
+        Ok(tournament)
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn entrants(&self) -> &[Participant] {
+        &self.entrants
+    }
+
+    /// The name of an entrant, numbered if the same computer player is entered more than once.
+    pub fn name(&self, entrant: usize) -> String {
+        let participant = &self.entrants[entrant];
+        let name = participant.name();
+        if self
+            .entrants
+            .iter()
+            .filter(|other| *other == participant)
+            .count()
+            == 1
+        {
+            return name.to_string();
+        }
+        let number = self.entrants[..entrant]
+            .iter()
+            .filter(|other| *other == participant)
+            .count()
+            + 1;
+        format!("{} {}", name, number)
+    }
+
+    /// All matches paired so far, in the order they are played.
+    pub fn matches(&self) -> &[TournamentMatch] {
+        &self.matches
+    }
+
+    pub fn standing(&self, entrant: usize) -> &Standing {
+        &self.standings[entrant]
+    }
+
+    /// The match that is played next, None once the tournament is over.
+    pub fn next_match(&self) -> Option<&TournamentMatch> {
+        self.matches.iter().find(|game| game.result.is_none())
+    }
+
+    /// The round that is currently played, or the last one once the tournament is over.
+    pub fn round(&self) -> u32 {
+        self.next_match()
+            .or(self.matches.last())
+            .map_or(1, |game| game.round)
+    }
+
+    /// Whether the entrant has lost too many matches to go on.
+    pub fn is_eliminated(&self, entrant: usize) -> bool {
+        self.format
+            .max_losses()
+            .is_some_and(|max_losses| self.standings[entrant].losses >= max_losses)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.next_match().is_none()
+    }
+
+    /// The winner of the tournament, once it is over.
+    pub fn champion(&self) -> Option<usize> {
+        if !self.is_over() {
+            return None;
+        }
+        self.ranking().first().copied()
+    }
+
+    /// All entrants from the best to the worst.
+    /// Players that are still in the tournament come first, then the most wins and the best point difference.
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..self.entrants.len()).collect();
+        ranking.sort_by_key(|entrant| {
+            let standing = &self.standings[*entrant];
+            (
+                self.is_eliminated(*entrant),
+                Reverse(standing.wins),
+                standing.points_lost as i64 - standing.points_won as i64,
+            )
+        });
+        ranking
+    }
+
+    /// Adds the result of the next match and pairs the next round once all matches of this one are played.
+    pub fn record(&mut self, winner: PlayerId, score: (u32, u32)) {
+        let Some(game) = self.matches.iter_mut().find(|game| game.result.is_none()) else {
+            eprintln!("Unable to record a match of a tournament that is over");
+            return;
+        };
+        game.result = Some((winner, score));
+
+        let [one, two] = game.players;
+        let (winner, loser) = match winner {
+            PlayerId::One => (one, two),
+            PlayerId::Two => (two, one),
+        };
+        self.standings[winner].wins += 1;
+        self.standings[loser].losses += 1;
+        for (entrant, won, lost) in [(one, score.0, score.1), (two, score.1, score.0)] {
+            self.standings[entrant].points_won += won;
+            self.standings[entrant].points_lost += lost;
+        }
+
+        if self.format != Format::RoundRobin && self.is_over() {
+            self.schedule_round();
+        }
+    }
+
+    /// Pairs everyone against everyone with the circle method,
+    /// so every entrant plays at most once per round.
+    fn schedule_round_robin(&mut self) {
+        let mut circle: Vec<Option<usize>> = (0..self.entrants.len()).map(Some).collect();
+        if circle.len() % 2 == 1 {
+            // The opponent of the empty slot sits the round out
+            circle.push(None);
+        }
+
+        let len = circle.len();
+        for round in 0..len - 1 {
+            for index in 0..len / 2 {
+                if let (Some(one), Some(two)) = (circle[index], circle[len - 1 - index]) {
+                    // Alternate the sides, so nobody always plays on the same one
+                    let players = if round % 2 == 0 {
+                        [one, two]
+                    } else {
+                        [two, one]
+                    };
+                    self.matches.push(TournamentMatch {
+                        round: round as u32 + 1,
+                        stage: Stage::Main,
+                        players,
+                        result: None,
+                    });
+                }
+            }
+            circle[1..].rotate_right(1);
+        }
+    }
+
+    /// Pairs the next round of an elimination tournament, if there is one.
+    fn schedule_round(&mut self) {
+        let round = self.matches.last().map_or(1, |game| game.round + 1);
+        let with_losses = |losses: u32| -> Vec<usize> {
+            (0..self.entrants.len())
+                .filter(|entrant| self.standings[*entrant].losses == losses)
+                .collect()
+        };
+        let winners = with_losses(0);
+
+        match self.format {
+            Format::SingleElimination => self.pair(&winners, round, Stage::Main),
+            Format::DoubleElimination => {
+                let losers = with_losses(1);
+                if winners.len() + losers.len() == 2 {
+                    // Played again if the winner of the losers bracket wins the first one,
+                    // because then both have lost once
+                    let players = [winners, losers].concat();
+                    self.matches.push(TournamentMatch {
+                        round,
+                        stage: Stage::GrandFinal,
+                        players: [players[0], players[1]],
+                        result: None,
+                    });
+                } else {
+                    self.pair(&winners, round, Stage::Main);
+                    self.pair(&losers, round, Stage::Losers);
+                }
+            }
+            Format::RoundRobin => {}
+        }
+    }
+
+    /// Pairs the best seeds against the worst, giving byes to the best seeds
+    /// until the number of players left is a power of two.
+    fn pair(&mut self, entrants: &[usize], round: u32, stage: Stage) {
+        if entrants.len() < 2 {
+            return;
+        }
+        let byes = entrants.len().next_power_of_two() - entrants.len();
+        let playing = &entrants[byes..];
+        for index in 0..playing.len() / 2 {
+            self.matches.push(TournamentMatch {
+                round,
+                stage,
+                players: [playing[index], playing[playing.len() - 1 - index]],
+                result: None,
+            });
+        }
+    }
+}
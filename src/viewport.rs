@@ -0,0 +1,64 @@
+use graphics::math::Matrix2d;
+use graphics::Transformed;
+use piston::RenderArgs;
+
+use crate::COURT_SIZE;
+
+/// Maps the logical court coordinates onto the window.
+/// The court is scaled to fit into the window while keeping its aspect ratio,
+/// the remaining space is filled with bars on the sides (letterboxing).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CourtViewport {
+    /// The window pixels per court unit.
+    pub scale: f64,
+    /// The position of the top left corner of the court in the window.
+    pub offset: [f64; 2],
+}
+
+impl CourtViewport {
+    pub fn new(window_size: [f64; 2]) -> Self {
+        let (court_width, court_height) = (COURT_SIZE.0 as f64, COURT_SIZE.1 as f64);
+        let scale = (window_size[0] / court_width).min(window_size[1] / court_height);
+
+        Self {
+            scale,
+            offset: [
+                (window_size[0] - court_width * scale) / 2.0,
+                (window_size[1] - court_height * scale) / 2.0,
+            ],
+        }
+    }
+
+    /// Changes the transform so that everything is drawn in court coordinates.
+    pub fn transform(&self, transform: Matrix2d) -> Matrix2d {
+        transform
+            .trans(self.offset[0], self.offset[1])
+            .scale(self.scale, self.scale)
+    }
+
+    /// Converts a position in the window, for example of the mouse cursor, into court coordinates.
+    /// Returns None while the window has no size, like when it is minimized.
+    pub fn window_to_court(&self, position: [f64; 2]) -> Option<[f64; 2]> {
+        if self.scale <= 0.0 {
+            return None;
+        }
+
+        Some([
+            (position[0] - self.offset[0]) / self.scale,
+            (position[1] - self.offset[1]) / self.scale,
+        ])
+    }
+
+    /// The area of the court in framebuffer pixels, used to cut off everything outside of the court.
+    pub fn scissor(&self, args: &RenderArgs) -> [u32; 4] {
+        // The framebuffer can have more pixels than the window on high dpi screens
+        let dpi = args.draw_size[0] as f64 / args.window_size[0];
+
+        [
+            (self.offset[0] * dpi).round() as u32,
+            (self.offset[1] * dpi).round() as u32,
+            ((COURT_SIZE.0 as f64) * self.scale * dpi).round() as u32,
+            ((COURT_SIZE.1 as f64) * self.scale * dpi).round() as u32,
+        ]
+    }
+}